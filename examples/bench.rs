@@ -0,0 +1,120 @@
+//! Compares the throughput of `Dumper::dump` with a line-at-a-time formatter built on `fmt`,
+//! like the one `hxx` used before its table-driven engine.
+//!
+//! Run with `cargo run --release --example bench [MiB]` (default 64 MiB of pseudo-random data).
+
+use std::fmt::Write as _;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+// Formats every line with `write!`, one byte at a time, and writes it on its own through a
+// `BufWriter`, using the default `xxd` layout (16 bytes per line, groups of 2)
+fn dump_with_fmt(bytes: &[u8], writer: impl Write) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let mut line = String::with_capacity(128);
+
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        write!(line, "{:08x}: ", i * 16).unwrap();
+
+        for (j, byte) in chunk.iter().enumerate() {
+            if j != 0 && j % 2 == 0 {
+                line.push(' ');
+            }
+
+            write!(line, "{byte:02x}").unwrap();
+        }
+
+        let width = |len: usize| len * 2 + len.div_ceil(2) - 1;
+        write!(
+            line,
+            "{:>padding$}  ",
+            "",
+            padding = width(16) - width(chunk.len())
+        )
+        .unwrap();
+
+        line.extend(chunk.iter().map(|&b| match b {
+            0x20..=0x7e => b as char,
+            _ => '.',
+        }));
+        line.push('\n');
+
+        writer.write_all(line.as_bytes())?;
+        line.clear();
+    }
+
+    writer.flush()
+}
+
+// Deterministic pseudo-random bytes (xorshift), so runs are comparable
+fn data(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        })
+        .collect()
+}
+
+// Runs `f` a few times, keeping the fastest run
+fn measure(mut f: impl FnMut()) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .expect("at least one run")
+}
+
+fn main() {
+    let mib: usize = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("size must be a number of MiB"))
+        .unwrap_or(64);
+
+    let bytes = data(mib << 20);
+    let mut output = Vec::with_capacity(bytes.len() * 5);
+
+    let dumper = hxx::Dumper::new();
+
+    // Both implementations must produce the same hex dump
+    dumper.dump(&bytes[..], &mut output).unwrap();
+    let expected = output.clone();
+
+    output.clear();
+    dump_with_fmt(&bytes, &mut output).unwrap();
+    assert!(output == expected, "outputs differ");
+
+    let baseline = measure(|| {
+        output.clear();
+        dump_with_fmt(&bytes, &mut output).unwrap();
+    });
+
+    let engine = measure(|| {
+        output.clear();
+        dumper.dump(&bytes[..], &mut output).unwrap();
+    });
+
+    let throughput = |elapsed: Duration| mib as f64 / elapsed.as_secs_f64();
+
+    println!(
+        "fmt:    {:>8.1?}  {:>8.1} MiB/s",
+        baseline,
+        throughput(baseline)
+    );
+    println!(
+        "engine: {:>8.1?}  {:>8.1} MiB/s",
+        engine,
+        throughput(engine)
+    );
+    println!(
+        "speedup: {:.1}x",
+        baseline.as_secs_f64() / engine.as_secs_f64()
+    );
+}
@@ -0,0 +1,202 @@
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A file written through a temporary file in the same directory, which only replaces the
+/// destination once committed.
+///
+/// A run that fails (or panics) before `commit` removes the temporary file, leaving the
+/// destination as it was.
+pub struct AtomicFile {
+    file: File,
+    path: PathBuf,
+    temp_path: PathBuf,
+    no_clobber: bool,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Starts writing a new file that will replace `path`, as if it was truncated.
+    ///
+    /// If `path` is a symbolic link, the file it points to is replaced rather than the link.
+    /// The permissions of an existing file at `path` are kept. If `no_clobber` is `true`,
+    /// committing fails if `path` exists by then.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if the temporary file cannot be created.
+    pub fn create(path: impl AsRef<Path>, no_clobber: bool) -> io::Result<Self> {
+        let path = &resolve_links(path.as_ref())?;
+        let temp_path = temp_path(path);
+
+        let file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+
+        let atomic = Self {
+            file,
+            path: path.to_path_buf(),
+            temp_path,
+            no_clobber,
+            committed: false,
+        };
+
+        if let Ok(metadata) = fs::metadata(path) {
+            atomic.file.set_permissions(metadata.permissions())?;
+        }
+
+        Ok(atomic)
+    }
+
+    /// Replaces the destination with everything written so far.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if the data cannot be synced to disk, or the
+    /// destination cannot be replaced (e.g., it was created meanwhile with `no_clobber`).
+    pub fn commit(mut self) -> io::Result<()> {
+        self.file.sync_all()?;
+
+        if self.no_clobber {
+            // Unlike renaming, linking fails if the destination exists
+            fs::hard_link(&self.temp_path, &self.path)?;
+            fs::remove_file(&self.temp_path)?;
+        } else {
+            fs::rename(&self.temp_path, &self.path)?;
+        }
+
+        self.committed = true;
+
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for AtomicFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+// Follows the symbolic links from `path` to the file they point to, which may not exist yet
+fn resolve_links(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+
+    // Bounded like the resolution done by the system, in case of a loop
+    for _ in 0..40 {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+
+                // Relative targets are relative to the directory of the link
+                path = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+
+    Err(io::Error::other("too many levels of symbolic links"))
+}
+
+// Hidden file next to `path`, so that it can be renamed onto `path` (renames cannot cross
+// file systems), unique to this process
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.{}.tmp", process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::test_path;
+
+    #[test]
+    fn test_commit_replaces_destination() {
+        let path = test_path("atomic-commit");
+        fs::write(&path, b"old contents").unwrap();
+
+        let mut file = AtomicFile::create(&path, false).unwrap();
+        file.write_all(b"new").unwrap();
+
+        // Nothing is visible until committed
+        assert_eq!(fs::read(&path).unwrap(), b"old contents");
+
+        file.commit().unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_drop_keeps_destination() {
+        let path = test_path("atomic-drop");
+        fs::write(&path, b"old contents").unwrap();
+
+        let mut file = AtomicFile::create(&path, false).unwrap();
+        file.write_all(b"new").unwrap();
+        let temp_path = file.temp_path.clone();
+        drop(file);
+
+        assert_eq!(fs::read(&path).unwrap(), b"old contents");
+        assert!(!temp_path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_no_clobber_commit_fails_if_created_meanwhile() {
+        let path = test_path("atomic-no-clobber");
+
+        let file = AtomicFile::create(&path, true).unwrap();
+        fs::write(&path, b"created meanwhile").unwrap();
+
+        assert!(file.commit().is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"created meanwhile");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_follows_symlink() {
+        let (target, link) = (test_path("atomic-link-target"), test_path("atomic-link"));
+        fs::write(&target, b"old contents").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut file = AtomicFile::create(&link, false).unwrap();
+        file.write_all(b"new").unwrap();
+        file.commit().unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+
+        fs::remove_file(&link).unwrap();
+        fs::remove_file(&target).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::iter;
+
+/// Character set used to show bytes in the text column of a hex dump.
+///
+/// Bytes that have no printable representation in the character set are shown with a
+/// placeholder character instead. Multi-byte encodings (UTF-8, UTF-16) show each character
+/// under its first byte, and mark the remaining bytes of the character and the bytes of
+/// invalid sequences distinctly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// 7-bit ASCII: only SP (0x20) to ~ (0x7e) are printable.
+    #[default]
+    Ascii,
+
+    /// EBCDIC, using the same translation table as `xxd -E`, restricted to printable ASCII.
+    Ebcdic,
+
+    /// ISO 8859-1 (Latin-1): printable ASCII and 0xa0 to 0xff, except the soft hyphen (0xad).
+    Latin1,
+
+    /// IBM PC code page 437, including the glyphs shown for control characters by DOS.
+    /// Only nul (0x00) and the non-breaking space (0xff) are not printable.
+    Cp437,
+
+    /// UTF-8, decoded across line boundaries.
+    Utf8,
+
+    /// UTF-16 little-endian, decoded across line boundaries in 2-byte units counted from the
+    /// start of the dump.
+    Utf16Le,
+
+    /// UTF-16 big-endian, decoded across line boundaries in 2-byte units counted from the
+    /// start of the dump.
+    Utf16Be,
+}
+
+/// Representation of a byte in the text column, as decoded by `Charset::decode_line`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Cell {
+    /// First byte of a printable character.
+    Char(char),
+
+    /// First byte of a character that has no printable representation.
+    Unprintable,
+
+    /// Remaining byte of a multi-byte character.
+    Continuation,
+
+    /// Byte of an invalid (or, at the end of input, incomplete) sequence.
+    Invalid,
+}
+
+impl Charset {
+    /// Returns the character representing `byte`, or `None` if it is not printable.
+    ///
+    /// For multi-byte encodings, only bytes that are a printable character on their own are
+    /// decoded (i.e., printable ASCII in UTF-8).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hxx::Charset;
+    ///
+    /// assert_eq!(Charset::Ascii.decode(b'A'), Some('A'));
+    /// assert_eq!(Charset::Ebcdic.decode(0xc1), Some('A'));
+    /// assert_eq!(Charset::Latin1.decode(0xe9), Some('é'));
+    /// assert_eq!(Charset::Cp437.decode(0x01), Some('☺'));
+    /// assert_eq!(Charset::Ascii.decode(0x00), None);
+    /// ```
+    pub fn decode(self, byte: u8) -> Option<char> {
+        match (self, byte) {
+            // Printable characters: SP (0x20) to ~ (0x7e)
+            (Self::Ascii | Self::Latin1 | Self::Cp437 | Self::Utf8, 0x20..=0x7e) => {
+                Some(byte as char)
+            }
+            (Self::Ascii | Self::Utf8 | Self::Utf16Le | Self::Utf16Be, _) => None,
+            (Self::Ebcdic, _) => match EBCDIC[byte as usize] {
+                0 => None,
+                c => Some(c as char),
+            },
+            // Latin-1 maps directly onto the first 256 Unicode code points; NBSP (0xa0) is kept
+            // as a space, while the soft hyphen (0xad) is usually invisible
+            (Self::Latin1, 0xa0..=0xff) if byte != 0xad => Some(byte as char),
+            (Self::Latin1, _) => None,
+            (Self::Cp437, 0x00 | 0xff) => None,
+            (Self::Cp437, 0x01..=0x1f) => Some(CP437_CONTROL[byte as usize]),
+            (Self::Cp437, 0x7f) => Some('⌂'),
+            (Self::Cp437, _) => Some(CP437_HIGH[byte as usize - 0x80]),
+        }
+    }
+
+    // Number of bytes around a line needed to decode the characters crossing its boundaries
+    pub(crate) fn context_len(self) -> usize {
+        match self {
+            // A UTF-8 sequence, or a UTF-16 surrogate pair, is at most 4 bytes long
+            Self::Utf8 | Self::Utf16Le | Self::Utf16Be => 3,
+            _ => 0,
+        }
+    }
+
+    // Decodes every byte of `line`, which starts `index` bytes into the dump and is surrounded
+    // by the bytes `before` and `after` it (up to `context_len` each)
+    pub(crate) fn decode_line(
+        self,
+        line: &[u8],
+        index: u64,
+        before: &[u8],
+        after: &[u8],
+    ) -> Vec<Cell> {
+        let mut cells = Vec::with_capacity(before.len() + line.len() + after.len());
+        let window = [before, line, after].concat();
+
+        match self {
+            Self::Utf8 => {
+                // Invalid chunks are maximal invalid subparts, so a lead byte always starts a
+                // new sequence, whatever precedes it
+                for chunk in window.utf8_chunks() {
+                    for c in chunk.valid().chars() {
+                        push_char(&mut cells, c, c.len_utf8());
+                    }
+
+                    cells.extend(iter::repeat_n(Cell::Invalid, chunk.invalid().len()));
+                }
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                // The window may start in the middle of a unit, which is outside of the line
+                let skip = (index - before.len() as u64) as usize % 2;
+                cells.extend(iter::repeat_n(Cell::Invalid, skip));
+
+                let units = window[skip..].chunks_exact(2);
+                let remainder = units.remainder().len();
+
+                let units = units.map(|unit| match self {
+                    Self::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+                    _ => u16::from_be_bytes([unit[0], unit[1]]),
+                });
+
+                for c in char::decode_utf16(units) {
+                    match c {
+                        Ok(c) => push_char(&mut cells, c, c.len_utf16() * 2),
+                        Err(_) => cells.extend([Cell::Invalid; 2]),
+                    }
+                }
+
+                cells.extend(iter::repeat_n(Cell::Invalid, remainder));
+            }
+            _ => cells.extend(
+                window
+                    .iter()
+                    .map(|&b| self.decode(b).map_or(Cell::Unprintable, Cell::Char)),
+            ),
+        }
+
+        cells.drain(..before.len());
+        cells.truncate(line.len());
+        cells
+    }
+}
+
+fn push_char(cells: &mut Vec<Cell>, c: char, len: usize) {
+    cells.push(match char_width(c) {
+        0 => Cell::Unprintable,
+        _ => Cell::Char(c),
+    });
+    cells.extend(iter::repeat_n(Cell::Continuation, len - 1));
+}
+
+// Number of terminal columns taken up by `c`: 0 for control, combining and format characters,
+// 2 for wide and fullwidth East Asian characters and emoji (by their main blocks), otherwise 1
+pub(crate) fn char_width(c: char) -> usize {
+    match c as u32 {
+        _ if c.is_control() => 0,
+        0x0300..=0x036f
+        | 0x0483..=0x0489
+        | 0x0591..=0x05bd
+        | 0x0610..=0x061a
+        | 0x064b..=0x065f
+        | 0x0e31
+        | 0x0e34..=0x0e3a
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x2028..=0x202e
+        | 0x2060..=0x2064
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f
+        | 0xfeff => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x2fffd
+        | 0x30000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+// ASCII equivalents of EBCDIC bytes, as translated by `xxd -E`; 0 marks non-printable bytes
+const EBCDIC: [u8; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2e, 0x3c, 0x28, 0x2b, 0x7c,
+    0x26, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x24, 0x2a, 0x29, 0x3b, 0x7e,
+    0x2d, 0x2f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x25, 0x5f, 0x3e, 0x3f,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x3a, 0x23, 0x40, 0x27, 0x3d, 0x22,
+    0x00, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0x5e, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x00, 0x00, 0x00, 0x5b, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5d, 0x00, 0x00,
+    0x7b, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x7d, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f, 0x50, 0x51, 0x52, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x5c, 0x00, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Glyphs shown for control characters by code page 437; nul (0x00) is never looked up
+#[rustfmt::skip]
+const CP437_CONTROL: [char; 32] = [
+    '\0', '☺', '☻', '♥', '♦', '♣', '♠', '•',
+    '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨',
+    '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+// Code page 437 characters for 0x80 to 0xff; 0xff (NBSP) is never looked up
+#[rustfmt::skip]
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç',
+    'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù',
+    'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º',
+    '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖',
+    '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟',
+    '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫',
+    '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ',
+    'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈',
+    '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
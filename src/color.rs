@@ -0,0 +1,70 @@
+use crate::Charset;
+
+// ANSI escape sequences used to color bytes by class, following the `xxd -R` scheme
+const NUL: &str = "\x1b[1;37m";
+const WHITESPACE: &str = "\x1b[1;33m";
+const PRINTABLE: &str = "\x1b[1;32m";
+const OTHER: &str = "\x1b[1;31m";
+const FF: &str = "\x1b[1;34m";
+
+const RESET: &str = "\x1b[0m";
+
+// Color of `byte`, by how `charset` shows it. Bytes of multi-byte encodings only make up a
+// character along with others, so they are classed as ASCII bytes instead
+fn byte_color(charset: Charset, byte: u8) -> &'static str {
+    let (charset, whitespace) = match charset {
+        // HT, CR, LF
+        Charset::Ebcdic => (charset, [0x05, 0x0d, 0x25]),
+        Charset::Latin1 | Charset::Cp437 => (charset, [0x09, 0x0a, 0x0d]),
+        _ => (Charset::Ascii, [0x09, 0x0a, 0x0d]),
+    };
+
+    match byte {
+        0x00 => NUL,
+        _ if charset.decode(byte).is_some() => PRINTABLE,
+        _ if whitespace.contains(&byte) => WHITESPACE,
+        0xff => FF,
+        _ => OTHER,
+    }
+}
+
+/// Colors the representations of bytes written to a line.
+///
+/// Escape sequences are only emitted when the color changes, and take up no columns, so
+/// alignment must be computed from the number of bytes rather than the length of the line.
+pub(crate) struct Painter {
+    enabled: bool,
+    charset: Charset,
+    current: Option<&'static str>,
+}
+
+impl Painter {
+    pub(crate) fn new(enabled: bool, charset: Charset) -> Self {
+        Self {
+            enabled,
+            charset,
+            current: None,
+        }
+    }
+
+    /// Switches to the color of `byte`, if needed, before its representation is written.
+    pub(crate) fn paint(&mut self, line: &mut Vec<u8>, byte: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        let color = byte_color(self.charset, byte);
+
+        if self.current != Some(color) {
+            line.extend_from_slice(color.as_bytes());
+            self.current = Some(color);
+        }
+    }
+
+    /// Restores the default color, if any color is in effect.
+    pub(crate) fn reset(&mut self, line: &mut Vec<u8>) {
+        if self.current.take().is_some() {
+            line.extend_from_slice(RESET.as_bytes());
+        }
+    }
+}
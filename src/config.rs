@@ -1,11 +1,240 @@
-use std::fs::File;
-use std::io::{self, Read, Write};
-use std::process;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{AtomicFile, Charset, Dumper, Error};
+
+/// Input source for hex processing operations.
+///
+/// Distinguishes sources that support random access from those that can only be
+/// read sequentially, so that seeking can be performed efficiently when possible.
+pub enum Input {
+    /// A sequential stream (e.g., stdin or a pipe). Skipped bytes are read and discarded.
+    Stream(Box<dyn Read>),
+
+    /// A seekable source (e.g., a regular file). Skipped bytes are never read.
+    Seekable(Box<dyn ReadSeek>),
+}
+
+/// Combination of `Read` and `Seek`, used for seekable input sources.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+impl Input {
+    /// Returns the number of bytes left from the current position of a seekable source, or
+    /// `None` for a stream, whose size is unknown.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if the underlying seek fails.
+    pub fn remaining(&mut self) -> io::Result<Option<u64>> {
+        match self {
+            Self::Stream(_) => Ok(None),
+            Self::Seekable(reader) => {
+                let position = reader.stream_position()?;
+                let end = reader.seek(SeekFrom::End(0))?;
+                reader.seek(SeekFrom::Start(position))?;
+
+                Ok(Some(end.saturating_sub(position)))
+            }
+        }
+    }
+
+    /// Moves to the given position in the input, returning the new absolute position.
+    ///
+    /// Seekable sources are repositioned directly. For streams, only forward movement is
+    /// possible, which is done by reading and discarding bytes; the returned position
+    /// is the number of bytes actually discarded.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if:
+    /// - The underlying seek or read fails.
+    /// - A stream is asked to move backwards or relative to its end.
+    pub fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Seekable(reader) => reader.seek(pos),
+            Self::Stream(reader) => {
+                let skip = match pos {
+                    SeekFrom::Start(n) => n,
+                    SeekFrom::Current(n) if n >= 0 => n as u64,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Unsupported,
+                            "cannot seek backwards or from end on non-seekable input",
+                        ));
+                    }
+                };
+
+                io::copy(&mut reader.take(skip), &mut io::sink())
+            }
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Stream(reader) => reader.read(buf),
+            Self::Seekable(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Output destination for hex processing operations.
+///
+/// Reverse operations write bytes at the offsets found in the hex dump, which requires
+/// random access to patch existing data or skip over gaps.
+pub enum Output {
+    /// A sequential stream (e.g., stdout or a pipe). Gaps are filled with zeros.
+    Stream(Box<dyn Write>),
+
+    /// A seekable destination (e.g., a regular file). Gaps are skipped, leaving existing
+    /// data untouched or creating holes past the end.
+    Seekable(Box<dyn WriteSeek>),
+
+    /// A seekable file that replaces its destination only once `Output::finish` is called,
+    /// so that a failed run never leaves a half-written output.
+    Atomic(AtomicFile),
+}
+
+/// Combination of `Write` and `Seek`, used for seekable output destinations.
+pub trait WriteSeek: Write + Seek {}
+
+impl<T: Write + Seek> WriteSeek for T {}
+
+impl Output {
+    /// Moves from the absolute position `current` to the absolute position `target`.
+    ///
+    /// Seekable destinations are repositioned directly. For streams, only forward movement
+    /// is possible, which is done by writing zeros.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if:
+    /// - The underlying seek or write fails.
+    /// - A stream is asked to move backwards.
+    pub fn seek(&mut self, current: u64, target: u64) -> io::Result<()> {
+        match self {
+            Self::Seekable(writer) => writer.seek(SeekFrom::Start(target)).map(|_| ()),
+            Self::Atomic(file) => file.seek(SeekFrom::Start(target)).map(|_| ()),
+            Self::Stream(_) if target < current => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek backwards on non-seekable output",
+            )),
+            Self::Stream(writer) => {
+                io::copy(&mut io::repeat(0).take(target - current), writer).map(|_| ())
+            }
+        }
+    }
+
+    /// Flushes the output, and commits it if written through a temporary file.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if the flush or commit fails.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Stream(mut writer) => writer.flush(),
+            Self::Seekable(mut writer) => writer.flush(),
+            Self::Atomic(file) => file.commit(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stream(writer) => writer.write(buf),
+            Self::Seekable(writer) => writer.write(buf),
+            Self::Atomic(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stream(writer) => writer.flush(),
+            Self::Seekable(writer) => writer.flush(),
+            Self::Atomic(file) => file.flush(),
+        }
+    }
+}
+
+/// Layout of the hex dump produced, or expected as input in reverse mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// `xxd` style: offset, grouped hex bytes, and ASCII representation.
+    #[default]
+    Normal,
+
+    /// Continuous hex stream without offsets, grouping, or ASCII representation.
+    ///
+    /// In reverse mode, any whitespace-separated hex is accepted regardless of line structure.
+    Plain,
+
+    /// C include file style: an `unsigned char` array followed by its length.
+    ///
+    /// In reverse mode, the elements of such an array are read back, with or without the
+    /// surrounding declarations.
+    Include,
+
+    /// Binary digit dump: like `Format::Normal`, but each byte is shown as 8 binary digits.
+    Bits,
+
+    /// Like `Format::Normal`, but each byte group is shown byte-swapped as a little-endian
+    /// word. Partial groups are right-aligned within the width of a full group.
+    ///
+    /// In reverse mode, `cols` and `byte_groups` must match those used for the hex dump.
+    LittleEndian,
+}
+
+impl Format {
+    /// Default number of bytes per line for this format.
+    pub fn default_cols(self) -> usize {
+        match self {
+            Self::Normal | Self::LittleEndian => 16,
+            Self::Plain => 30,
+            Self::Include => 12,
+            Self::Bits => 6,
+        }
+    }
+
+    /// Default number of bytes per group for this format.
+    pub fn default_byte_groups(self) -> usize {
+        match self {
+            Self::Bits => 1,
+            Self::LittleEndian => 4,
+            _ => 2,
+        }
+    }
+}
+
+/// Outcome of parsing command-line arguments with `Config::build`.
+///
+/// The library never prints or exits on its own: showing the help or version text, and
+/// choosing an exit status, is left to the caller.
+pub enum Action {
+    /// Perform a hex dump or reverse hex dump with the given `Config`, boxed as it is much
+    /// larger than the text of the other variants.
+    Run(Box<Config>),
+
+    /// Show the usage information (`-h`), as returned by `usage`.
+    Help(String),
+
+    /// Show the program name and version (`-v`), as returned by `version`.
+    Version(String),
+}
 
 /// Configuration for hex dumping and reverse hex dumping operations.
 ///
 /// Defines the behavior of the hex processing functions, including formatting options,
 /// direction of operation (dump or reverse), and I/O sources.
+///
+/// This is the command-line layer: formatting itself is done by the `Dumper` returned by
+/// `Config::dumper`, which can also be used on its own.
 pub struct Config {
     /// Number of bytes to display per line in the hex dump.
     pub cols: usize,
@@ -13,20 +242,99 @@ pub struct Config {
     /// Number of bytes to group together in the hex output.
     pub byte_groups: usize,
 
+    /// Layout of the hex dump.
+    pub format: Format,
+
+    /// If `true`, runs of nul lines are replaced by a single `*` line, like `xxd`.
+    pub autoskip: bool,
+
+    /// If `true`, runs of any repeated lines are replaced by a single `*` line, like `hexdump`.
+    pub squeeze: bool,
+
+    /// If `true`, hex digits and offsets are shown in uppercase.
+    pub uppercase: bool,
+
+    /// Value added to every displayed offset (e.g., the load address of a memory image).
+    ///
+    /// In reverse mode, subtracted from the offsets found in the hex dump.
+    pub display_offset: u64,
+
+    /// If `true`, offsets are shown (or, in reverse mode, parsed) in decimal instead of hex.
+    pub decimal: bool,
+
+    /// Minimum number of digits of the displayed offsets. `None` (`auto`) fits every offset
+    /// when the size of the input is known (a seekable input), and falls back to 8 digits.
+    ///
+    /// In reverse mode, offsets of any width are parsed.
+    pub offset_width: Option<usize>,
+
+    /// If `true`, bytes are colored by class (nul, printable in `charset`, whitespace, 0xff,
+    /// other) using ANSI escape sequences, like `xxd -R`.
+    pub color: bool,
+
+    /// Character set used to show bytes in the text column.
+    pub charset: Charset,
+
+    /// Character shown in the text column for bytes that are not printable in `charset`.
+    pub placeholder: char,
+
+    /// Variable name used by `Format::Include`. `None` omits the array declarations.
+    pub name: Option<String>,
+
+    /// If `true`, variable names used by `Format::Include` are capitalized.
+    pub capitalize: bool,
+
+    /// Position in the input to start dumping from. `None` starts at the current position.
+    ///
+    /// In reverse mode, the displacement added to (or, for `SeekFrom::End`, subtracted from)
+    /// the offsets found in the hex dump.
+    pub seek: Option<SeekFrom>,
+
+    /// Maximum number of bytes to dump. `None` dumps until the end of input.
+    pub len: Option<u64>,
+
     /// If `true`, performs a reverse hex dump (hex -> binary); otherwise, (binary -> hex).
     pub reverse: bool,
 
+    /// Number of threads formatting the hex dump or, in reverse mode, parsing hex dumps with
+    /// offsets. The output is the same whatever the number.
+    pub jobs: usize,
+
+    /// If `true`, dumps every one of `files` after a header line with its name and size,
+    /// instead of `input`.
+    ///
+    /// In reverse mode, recreates the files named by the headers of `input` in `directory`,
+    /// instead of writing to `output`.
+    pub multi: bool,
+
+    /// Files of a multi-file hex dump, dumped one after another.
+    pub files: Vec<PathBuf>,
+
+    /// If `true`, offsets of a multi-file hex dump continue from one file to the next, instead
+    /// of restarting at 0. In reverse mode, this must match the hex dump.
+    pub continuous: bool,
+
+    /// Directory the files of a multi-file hex dump are recreated in, in reverse mode. `None`
+    /// stands for the current directory.
+    pub directory: Option<PathBuf>,
+
     /// Input source to read from (e.g., file or stdin).
-    pub input: Box<dyn Read>,
+    pub input: Input,
 
     /// Output destination to write to (e.g., file or stdout).
-    pub output: Box<dyn Write>,
+    pub output: Output,
 }
 
 impl Config {
     /// Constructs a `Config` from an iterator of command-line arguments.
     ///
     /// Parses arguments to determine formatting options, input/output streams, mode (dump or reverse), etc.
+    /// If help (`-h`) or version (`-v`) information is requested instead, parsing stops and the
+    /// text to show is returned, without printing anything or exiting.
+    ///
+    /// Arguments follow GNU conventions: long options (`--cols 8` or `--cols=8`), clustered
+    /// short options with attached values (`-ac8`), options after positional arguments, `--`
+    /// to end options, and `-` for stdin or stdout. Paths need not be valid UTF-8.
     ///
     /// `program` should be the name of the executable.
     ///
@@ -41,182 +349,866 @@ impl Config {
     ///     "4".to_string(),
     /// ];
     ///
-    /// let config = hxx::Config::build(args.into_iter(), "hxx").unwrap_or_else(|err| {
-    ///     eprintln!("Error: {err}");
-    ///     std::process::exit(1);
-    /// });
+    /// let Ok(hxx::Action::Run(config)) = hxx::Config::build(args, "hxx") else {
+    ///     panic!("expected a configuration to run");
+    /// };
     ///
     /// assert_eq!(config.cols, 40);
     /// assert_eq!(config.byte_groups, 4);
     /// ```
     ///
-    /// Using `env::args()` directly:
+    /// Using `env::args_os()` directly:
     /// ```
-    /// let mut args = std::env::args();
+    /// let mut args = std::env::args_os();
     ///
-    /// let program = args.next().unwrap_or_else(|| "hxx".to_string());
+    /// let program = args
+    ///     .next()
+    ///     .map(|program| program.to_string_lossy().into_owned())
+    ///     .unwrap_or_else(|| "hxx".to_string());
+    ///
+    /// match hxx::Config::build(args, &program) {
+    ///     Ok(hxx::Action::Run(config)) => {
+    ///         // Default values
+    ///         assert_eq!(config.cols, 16);
+    ///         assert_eq!(config.byte_groups, 2);
+    ///     }
+    ///     Ok(hxx::Action::Help(text) | hxx::Action::Version(text)) => println!("{text}"),
+    ///     Err(err) => {
+    ///         eprintln!("Error: {err}");
+    ///         std::process::exit(1);
+    ///     }
+    /// }
+    /// ```
     ///
-    /// let config = hxx::Config::build(args, &program).unwrap_or_else(|err| {
-    ///     eprintln!("Error: {err}");
-    ///     std::process::exit(1);
-    /// });;
+    /// Requesting help:
+    /// ```
+    /// let action = hxx::Config::build(["--help"], "hxx").unwrap();
     ///
-    /// // Default values
-    /// assert_eq!(config.cols, 16);
-    /// assert_eq!(config.byte_groups, 2);
+    /// assert!(matches!(action, hxx::Action::Help(text) if text.starts_with("Usage:")));
     /// ```
     ///
     /// # Error
     ///
     /// This function returns an error if:
     /// - An unrecognized flag is supplied.
-    /// - A flag is given an invalid value.
+    /// - A flag is given an invalid value, or a value it does not expect.
+    /// - More than two positional arguments are supplied.
     /// - The input file cannot be opened.
     /// - The output file cannot be created or opened for writing.
-    pub fn build<T: Iterator<Item = String>>(args: T, program: &str) -> Result<Self, String> {
-        let mut cols: usize = 16;
-        let mut byte_groups: usize = 2;
+    /// - The output file already exists, and `--no-clobber` is supplied.
+    /// - The input and output files are the same file.
+    pub fn build<T, A>(args: T, program: &str) -> Result<Action, Error>
+    where
+        T: IntoIterator<Item = A>,
+        A: Into<OsString>,
+    {
+        let mut cols: Option<usize> = None;
+        let mut byte_groups: Option<usize> = None;
+        let mut format = Format::Normal;
+        let mut autoskip = false;
+        let mut squeeze = false;
+        let mut uppercase = false;
+        let mut display_offset = 0;
+        let mut decimal = false;
+        let mut offset_width = Some(8);
+        let mut color = None;
+        let mut charset = Charset::Ascii;
+        let mut placeholder = '.';
+        let mut name = None;
+        let mut capitalize = false;
+        let mut seek = None;
+        let mut len = None;
         let mut reverse = false;
+        let mut jobs = 1;
+        let mut multi = false;
+        let mut continuous = false;
+        let mut append = false;
+        let mut no_clobber = false;
 
-        let mut args = args.peekable();
-
-        // Peekable allows for flag parsing without consuming potential file/path arguments
-        while let Some(arg) = args.peek() {
-            if arg.starts_with("-") {
-                // Next is guaranteed after peek; unwrap is safe
-                let flag_name = args.next().unwrap();
-
-                if let Some(flag) = FLAG_REGISTRY.iter().find(|flag| flag.name == flag_name) {
-                    match flag.name {
-                        // Flags expecting a proceeding value argument
-                        "-c" => {
-                            cols = Self::parse_value(args.next())?;
-                        }
-                        "-g" => {
-                            byte_groups = Self::parse_value(args.next())?;
-                        }
-                        "-r" => {
-                            reverse = true;
-                        }
-                        // No value argument expected
-                        _ => (flag.run)(&program),
-                    }
-                } else {
-                    return Err("unknown flag provided".into());
+        let (flags, positional) = parse_args(args)?;
+
+        for (flag, value) in flags {
+            match flag.long {
+                // Flags expecting a value argument
+                "cols" => {
+                    cols = Some(Self::parse_value(value)?);
                 }
-            } else {
-                // No remaining flags to process
-                break;
+                "groupsize" => {
+                    byte_groups = Some(Self::parse_value(value)?);
+                }
+                "seek" => {
+                    seek = Some(Self::parse_seek(value)?);
+                }
+                "len" => {
+                    len = Some(Self::parse_len(value)?);
+                }
+                "plain" => {
+                    format = Format::Plain;
+                }
+                "include" => {
+                    format = Format::Include;
+                }
+                "bits" => {
+                    format = Format::Bits;
+                }
+                "little-endian" => {
+                    format = Format::LittleEndian;
+                }
+                "autoskip" => {
+                    autoskip = !autoskip;
+                }
+                "squeeze" => {
+                    squeeze = !squeeze;
+                }
+                "uppercase" => {
+                    uppercase = true;
+                }
+                "offset" => {
+                    display_offset = Self::parse_len(value)?;
+                }
+                "decimal" => {
+                    decimal = true;
+                }
+                "offset-width" => {
+                    offset_width = Self::parse_offset_width(value)?;
+                }
+                "color" => {
+                    color = Self::parse_color(value)?;
+                }
+                "ebcdic" => {
+                    charset = Charset::Ebcdic;
+                }
+                "charset" => {
+                    charset = Self::parse_charset(value)?;
+                }
+                "placeholder" => {
+                    placeholder = Self::parse_placeholder(value)?;
+                }
+                "name" => {
+                    name = Some(value.ok_or_else(missing_value)?);
+                }
+                "capitalize" => {
+                    capitalize = true;
+                }
+                "reverse" => {
+                    reverse = true;
+                }
+                "jobs" => {
+                    jobs = Self::parse_value(value)?;
+                }
+                "multi" => {
+                    multi = true;
+                }
+                "continuous" => {
+                    continuous = true;
+                }
+                "append" => {
+                    append = true;
+                }
+                "no-clobber" => {
+                    no_clobber = true;
+                }
+                // Parsing stops, leaving it to the caller to show the requested information
+                "help" => return Ok(Action::Help(usage(program))),
+                "version" => return Ok(Action::Version(version(program))),
+                _ => unreachable!("unhandled flag: --{}", flag.long),
             }
         }
 
-        // Read from file if provided; fallback to stdin
-        let input: Box<dyn Read> = if let Some(file_path) = args.next() {
-            let file =
-                File::open(file_path).map_err(|err| format!("failed to open file: {err}"))?;
-            Box::new(file)
+        // Like `xxd`, only whole groups of a power of 2 bytes are byte-swapped
+        if format == Format::LittleEndian
+            && byte_groups.is_some_and(|byte_groups| !byte_groups.is_power_of_two())
+        {
+            return Err(Error::usage(
+                "number of octets per group must be a power of 2 with -e",
+            ));
+        }
+
+        // Reversing writes the bytes at the offsets of the hex dump, which appending would ignore
+        if append && reverse {
+            return Err(Error::usage("--append cannot be used with --reverse"));
+        }
+
+        // Files are dumped (or recreated) whole
+        if multi && (seek.is_some() || len.is_some()) {
+            return Err(Error::usage("--seek and --len cannot be used with --multi"));
+        }
+
+        // Every positional argument of a multi-file hex dump is a file to dump
+        let (positional, files) = match multi && !reverse {
+            true => (Vec::new(), positional),
+            false => (positional, Vec::new()),
+        };
+
+        let files = files
+            .into_iter()
+            .map(|path| match path == "-" {
+                true => Err(Error::usage("stdin cannot be dumped with --multi")),
+                false => Ok(PathBuf::from(path)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if multi && !reverse && files.is_empty() {
+            return Err(Error::usage("no files provided for --multi"));
+        }
+
+        // `-` stands for stdin or stdout
+        let mut positional = positional
+            .into_iter()
+            .map(|path| (path != "-").then(|| PathBuf::from(path)));
+
+        let input_path = positional.next().flatten();
+        let mut output_path = positional.next().flatten();
+
+        if positional.next().is_some() {
+            return Err(Error::usage("too many arguments provided"));
+        }
+
+        // The files of a multi-file hex dump are recreated in the directory given as outfile
+        let directory = match multi && reverse {
+            true => output_path.take(),
+            false => None,
+        };
+
+        // Read from file if provided; fallback to stdin, unless dumping `files`
+        let input = if multi && !reverse {
+            Input::Stream(Box::new(io::empty()))
+        } else if let Some(file_path) = &input_path {
+            // Variable name for C include output defaults to the input path, as given
+            name.get_or_insert_with(|| file_path.to_string_lossy().into_owned());
+
+            let file = File::open(file_path).map_err(Error::io("failed to open file"))?;
+
+            match is_seekable(&file) {
+                true => Input::Seekable(Box::new(file)),
+                false => Input::Stream(Box::new(file)),
+            }
         } else {
-            Box::new(io::stdin().lock())
+            Input::Stream(Box::new(io::stdin().lock()))
         };
 
+        // Color is only enabled automatically when writing to a terminal, unless disabled
+        // through `NO_COLOR` (https://no-color.org)
+        let color = color.unwrap_or_else(|| {
+            output_path.is_none()
+                && io::stdout().is_terminal()
+                && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        });
+
         // Write to file if provided; fallback to stdout
-        let output: Box<dyn Write> = if let Some(file_path) = args.next() {
-            let file = if let Ok(file) = File::options().append(true).open(&file_path) {
-                file
-            } else {
-                // Create file if it doesn't exist
-                File::create(&file_path).map_err(|err| format!("failed to create file: {err}"))?
-            };
+        let output = if let Some(file_path) = output_path {
+            let metadata = fs::metadata(&file_path).ok();
+
+            if metadata.is_some() && no_clobber {
+                return Err(Error::usage("output file already exists"));
+            }
+
+            // Reading and writing the same file would overwrite data before it is read
+            if metadata.as_ref().is_some_and(|metadata| metadata.is_file())
+                && input_path
+                    .as_ref()
+                    .is_some_and(|input_path| same_file(input_path, &file_path))
+            {
+                return Err(Error::usage("input and output are the same file"));
+            }
+
+            // Devices, pipes, etc. cannot be replaced, so they are written to directly
+            let device = metadata.is_some_and(|metadata| !metadata.is_file());
 
-            Box::new(file)
+            // Files are patched in place or appended to, keeping their data, links and holes.
+            // Only new hex dumps replace the file, once complete
+            if reverse || append || device {
+                let file = File::options()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .append(append)
+                    .open(&file_path)
+                    .map_err(Error::io("failed to open file"))?;
+
+                match is_seekable(&file) {
+                    true => Output::Seekable(Box::new(file)),
+                    false => Output::Stream(Box::new(file)),
+                }
+            } else {
+                Output::Atomic(
+                    AtomicFile::create(&file_path, no_clobber)
+                        .map_err(Error::io("failed to create file"))?,
+                )
+            }
         } else {
-            Box::new(io::stdout().lock())
+            Output::Stream(Box::new(io::stdout().lock()))
         };
 
-        Ok(Self {
-            cols,
-            byte_groups,
+        Ok(Action::Run(Box::new(Self {
+            // Unless specified, the number of columns and groups depends on the format
+            cols: cols.unwrap_or(format.default_cols()),
+            byte_groups: byte_groups.unwrap_or(format.default_byte_groups()),
+            format,
+            autoskip,
+            squeeze,
+            uppercase,
+            display_offset,
+            decimal,
+            offset_width,
+            color,
+            charset,
+            placeholder,
+            name,
+            capitalize,
+            seek,
+            len,
             reverse,
+            jobs,
+            multi,
+            files,
+            continuous,
+            directory,
             input,
             output,
-        })
+        })))
+    }
+
+    /// Returns a `Dumper` with the formatting options of this `Config`.
+    ///
+    /// `seek`, `len` and the I/O sources are not part of the formatting, and are applied by
+    /// `hex_dump` around the `Dumper`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let config = hxx::Config {
+    ///     cols: 4,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(config.dumper().format_line(b"hxx", 0), "00000000: 6878 78    hxx");
+    /// ```
+    pub fn dumper(&self) -> Dumper {
+        let dumper = Dumper::new()
+            .format(self.format)
+            .cols(self.cols)
+            .byte_groups(self.byte_groups)
+            .autoskip(self.autoskip)
+            .squeeze(self.squeeze)
+            .uppercase(self.uppercase)
+            .display_offset(self.display_offset)
+            .decimal(self.decimal)
+            .color(self.color)
+            .charset(self.charset)
+            .placeholder(self.placeholder)
+            .capitalize(self.capitalize)
+            .jobs(self.jobs);
+
+        // The width of `auto` depends on the input, so it is left to `hex_dump`
+        let dumper = match self.offset_width {
+            Some(offset_width) => dumper.offset_width(offset_width),
+            None => dumper,
+        };
+
+        match &self.name {
+            Some(name) => dumper.name(name),
+            None => dumper,
+        }
     }
 
-    fn parse_value(value: Option<String>) -> Result<usize, String> {
-        match value.ok_or("missing value for flag")?.parse::<usize>() {
+    fn parse_value(value: Option<String>) -> Result<usize, Error> {
+        match value.ok_or_else(missing_value)?.parse::<usize>() {
             Ok(value) if (1..=256).contains(&value) => Ok(value),
-            _ => Err("invalid value for flag".into()),
+            _ => Err(invalid_value()),
+        }
+    }
+
+    // Accepts `[+|-]offset`: absolute, relative to the current position (`+`), or
+    // relative to the end of input (`-`)
+    fn parse_seek(value: Option<String>) -> Result<SeekFrom, Error> {
+        let value = value.ok_or_else(missing_value)?;
+
+        let (relative, value) = match value.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, value.as_str()),
+        };
+
+        let (from_end, value) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+
+        let offset = parse_number(value).ok_or_else(invalid_value)?;
+
+        if from_end {
+            let offset = i64::try_from(offset).map_err(|_| invalid_value())?;
+            Ok(SeekFrom::End(-offset))
+        } else if relative {
+            let offset = i64::try_from(offset).map_err(|_| invalid_value())?;
+            Ok(SeekFrom::Current(offset))
+        } else {
+            Ok(SeekFrom::Start(offset))
+        }
+    }
+
+    // `None` leaves the width to the size of the input
+    fn parse_offset_width(value: Option<String>) -> Result<Option<usize>, Error> {
+        match value.ok_or_else(missing_value)?.as_str() {
+            "auto" => Ok(None),
+            // Enough for the 20 decimal digits of `u64::MAX`
+            value => match value.parse::<usize>() {
+                Ok(width) if (1..=20).contains(&width) => Ok(Some(width)),
+                _ => Err(invalid_value()),
+            },
+        }
+    }
+
+    // `None` leaves the decision to whether the output is a terminal
+    fn parse_color(value: Option<String>) -> Result<Option<bool>, Error> {
+        match value.ok_or_else(missing_value)?.as_str() {
+            "always" => Ok(Some(true)),
+            "never" => Ok(Some(false)),
+            "auto" => Ok(None),
+            _ => Err(invalid_value()),
+        }
+    }
+
+    fn parse_charset(value: Option<String>) -> Result<Charset, Error> {
+        match value.ok_or_else(missing_value)?.as_str() {
+            "ascii" => Ok(Charset::Ascii),
+            "ebcdic" => Ok(Charset::Ebcdic),
+            "latin1" => Ok(Charset::Latin1),
+            "cp437" => Ok(Charset::Cp437),
+            "utf8" => Ok(Charset::Utf8),
+            "utf16le" => Ok(Charset::Utf16Le),
+            "utf16be" => Ok(Charset::Utf16Be),
+            _ => Err(invalid_value()),
+        }
+    }
+
+    // A single character, which must be printable to keep the text column aligned
+    fn parse_placeholder(value: Option<String>) -> Result<char, Error> {
+        let value = value.ok_or_else(missing_value)?;
+        let mut chars = value.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_control() => Ok(c),
+            _ => Err(invalid_value()),
+        }
+    }
+
+    fn parse_len(value: Option<String>) -> Result<u64, Error> {
+        parse_number(&value.ok_or_else(missing_value)?).ok_or_else(invalid_value)
+    }
+}
+
+impl Default for Config {
+    /// Default `xxd` formatting, reading from `stdin` and writing to `stdout`.
+    fn default() -> Self {
+        Self {
+            cols: 16,
+            byte_groups: 2,
+            format: Format::Normal,
+            autoskip: false,
+            squeeze: false,
+            uppercase: false,
+            display_offset: 0,
+            decimal: false,
+            offset_width: Some(8),
+            color: false,
+            charset: Charset::Ascii,
+            placeholder: '.',
+            name: None,
+            capitalize: false,
+            seek: None,
+            len: None,
+            reverse: false,
+            jobs: 1,
+            multi: false,
+            files: Vec::new(),
+            continuous: false,
+            directory: None,
+            // Unlocked handles, so that multiple defaults can coexist across threads
+            input: Input::Stream(Box::new(io::stdin())),
+            output: Output::Stream(Box::new(io::stdout())),
+        }
+    }
+}
+
+// Parses an unsigned number in decimal or, with a `0x` prefix, hexadecimal
+fn parse_number(value: &str) -> Option<u64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+// Whether `file` supports random access: regular files and block devices (e.g., a flash
+// partition being patched), unlike pipes, terminals and other character devices
+pub(crate) fn is_seekable(file: &File) -> bool {
+    let Ok(metadata) = file.metadata() else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if metadata.file_type().is_block_device() {
+            return true;
+        }
+    }
+
+    metadata.is_file()
+}
+
+// Whether two paths refer to the same file, including through links
+fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if let (Ok(a), Ok(b)) = (fs::metadata(&a), fs::metadata(&b)) {
+            return a.dev() == b.dev() && a.ino() == b.ino();
         }
     }
+
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
 }
 
 struct Flag {
-    name: &'static str,
+    short: Option<char>,
+    long: &'static str,
+    // Name of the value expected by the flag, if any
+    value: Option<&'static str>,
     description: &'static str,
-    run: fn(&str),
 }
 
 const FLAG_REGISTRY: &[Flag] = &[
     Flag {
-        name: "-c",
-        description: "cols      format <cols> octets per line (value must be in range 1..=256). Default 16.",
-        run: noop,
+        short: Some('c'),
+        long: "cols",
+        value: Some("cols"),
+        description: "format <cols> octets per line (value must be in range 1..=256). Default 16 (-i: 12, -p: 30, -b: 6).",
+    },
+    Flag {
+        short: Some('g'),
+        long: "groupsize",
+        value: Some("bytes"),
+        description: "number of octets per group in normal output (value must be in range 1..=256). Default 2 (-e: 4, -b: 1).",
+    },
+    Flag {
+        short: Some('p'),
+        long: "plain",
+        value: None,
+        description: "output in plain hexdump style: continuous hex without offsets or ASCII.",
+    },
+    Flag {
+        short: Some('a'),
+        long: "autoskip",
+        value: None,
+        description: "toggle autoskip: a single '*' replaces nul-lines. Default off.",
+    },
+    Flag {
+        short: Some('S'),
+        long: "squeeze",
+        value: None,
+        description: "toggle squeeze: a single '*' replaces repeated lines. Default off.",
+    },
+    Flag {
+        short: Some('b'),
+        long: "bits",
+        value: None,
+        description: "binary digit dump. Default hex.",
+    },
+    Flag {
+        short: Some('e'),
+        long: "little-endian",
+        value: None,
+        description: "little-endian dump. In reverse, -c and -g must match the dump.",
+    },
+    Flag {
+        short: Some('i'),
+        long: "include",
+        value: None,
+        description: "output in C include file style.",
+    },
+    Flag {
+        short: Some('u'),
+        long: "uppercase",
+        value: None,
+        description: "use upper case hex letters.",
+    },
+    Flag {
+        short: Some('o'),
+        long: "offset",
+        value: Some("off"),
+        description: "add <off> to the displayed file position (reverse: subtract).",
+    },
+    Flag {
+        short: Some('d'),
+        long: "decimal",
+        value: None,
+        description: "show offset in decimal instead of hex.",
+    },
+    Flag {
+        short: Some('w'),
+        long: "offset-width",
+        value: Some("width"),
+        description: "show offsets with at least <width> digits (value must be in range 1..=20), or 'auto' to fit the input size. Default 8.",
+    },
+    Flag {
+        short: Some('R'),
+        long: "color",
+        value: Some("when"),
+        description: "colorize the output; <when> can be 'always', 'auto' or 'never'. Default 'auto'.",
+    },
+    Flag {
+        short: Some('E'),
+        long: "ebcdic",
+        value: None,
+        description: "show characters in EBCDIC. Default ASCII.",
+    },
+    Flag {
+        short: Some('t'),
+        long: "charset",
+        value: Some("charset"),
+        description: "show characters in <charset>: 'ascii', 'ebcdic', 'latin1', 'cp437', 'utf8', 'utf16le' or 'utf16be'. Default 'ascii'.",
+    },
+    Flag {
+        short: Some('P'),
+        long: "placeholder",
+        value: Some("char"),
+        description: "show non-printable bytes as <char>. Default '.'.",
+    },
+    Flag {
+        short: Some('n'),
+        long: "name",
+        value: Some("name"),
+        description: "set the variable name used in C include output (-i).",
+    },
+    Flag {
+        short: Some('C'),
+        long: "capitalize",
+        value: None,
+        description: "capitalize variable names in C include file style (-i).",
+    },
+    Flag {
+        short: Some('s'),
+        long: "seek",
+        value: Some("seek"),
+        description: "start at [+][-]<seek> bytes abs. (or +: rel., -: from end) infile offset.",
+    },
+    Flag {
+        short: Some('l'),
+        long: "len",
+        value: Some("len"),
+        description: "stop after <len> octets.",
+    },
+    Flag {
+        short: Some('r'),
+        long: "reverse",
+        value: None,
+        description: "reverse operation: convert (or patch) hexdump into binary. -s adds <seek> to file positions.",
+    },
+    Flag {
+        short: Some('j'),
+        long: "jobs",
+        value: Some("jobs"),
+        description: "format (reverse: parse, except -p and -i) with <jobs> threads (value must be in range 1..=256). Default 1.",
+    },
+    Flag {
+        short: Some('m'),
+        long: "multi",
+        value: None,
+        description: "dump each <file> after a '# <name> (<size> bytes)' header (reverse: recreate the files in <directory>).",
+    },
+    Flag {
+        short: None,
+        long: "continuous",
+        value: None,
+        description: "continue offsets across the files of -m instead of restarting them at 0 (reverse: must match the dump).",
     },
     Flag {
-        name: "-g",
-        description: "bytes     number of octets per group in normal output (value must be in range 1..=256). Default 2.",
-        run: noop,
+        short: None,
+        long: "append",
+        value: None,
+        description: "append the hex dump to <outfile> instead of replacing it.",
     },
     Flag {
-        name: "-r",
-        description: "          reverse operation: convert (or patch) hexdump into binary.",
-        run: noop,
+        short: None,
+        long: "no-clobber",
+        value: None,
+        description: "fail if <outfile> already exists.",
     },
     Flag {
-        name: "-h",
-        description: "          print this summary.",
-        run: print_usage,
+        short: Some('h'),
+        long: "help",
+        value: None,
+        description: "print this summary.",
     },
     Flag {
-        name: "-v",
-        description: "          show version.",
-        run: print_version,
+        short: Some('v'),
+        long: "version",
+        value: None,
+        description: "show version.",
     },
 ];
 
-fn noop(_program: &str) {}
+// A flag of `FLAG_REGISTRY` found in the arguments, with its value if it expects one
+type ParsedFlag = (&'static Flag, Option<String>);
+
+// Splits GNU-style arguments into flags and positional arguments. Flags can appear anywhere
+// before `--`, as long options (`--cols 8`, `--cols=8`) or as clusters of short options
+// (`-rp`, `-c8`, `-ac 8`), the last of which may take a value
+fn parse_args<T, A>(args: T) -> Result<(Vec<ParsedFlag>, Vec<OsString>), Error>
+where
+    T: IntoIterator<Item = A>,
+    A: Into<OsString>,
+{
+    let mut flags = Vec::new();
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positional.extend(args.by_ref());
+            break;
+        }
+
+        // `-` alone is a positional argument, standing for stdin or stdout
+        if arg == "-" || !arg.as_encoded_bytes().starts_with(b"-") {
+            positional.push(arg);
+            continue;
+        }
+
+        // Paths can be any OS string, but flags and their values must be valid UTF-8
+        let arg = arg
+            .into_string()
+            .map_err(|_| Error::usage("invalid flag provided"))?;
+
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+
+            let flag = FLAG_REGISTRY
+                .iter()
+                .find(|flag| flag.long == name)
+                .ok_or_else(|| Error::usage(format!("unknown flag provided: --{name}")))?;
+
+            let value = match (flag.value, value) {
+                (Some(_), None) => Some(next_value(&mut args)?),
+                (None, Some(_)) => {
+                    return Err(Error::usage(format!("unexpected value for flag: --{name}")));
+                }
+                (_, value) => value,
+            };
+
+            flags.push((flag, value));
+        } else {
+            for (i, c) in arg.char_indices().skip(1) {
+                let flag = FLAG_REGISTRY
+                    .iter()
+                    .find(|flag| flag.short == Some(c))
+                    .ok_or_else(|| Error::usage(format!("unknown flag provided: -{c}")))?;
+
+                if flag.value.is_none() {
+                    flags.push((flag, None));
+                    continue;
+                }
+
+                // The rest of the argument, if any, is the value
+                let value = match &arg[i + c.len_utf8()..] {
+                    "" => next_value(&mut args)?,
+                    rest => rest.to_string(),
+                };
+
+                flags.push((flag, Some(value)));
+                break;
+            }
+        }
+    }
+
+    Ok((flags, positional))
+}
+
+fn next_value(args: &mut impl Iterator<Item = OsString>) -> Result<String, Error> {
+    args.next()
+        .ok_or_else(missing_value)?
+        .into_string()
+        .map_err(|_| invalid_value())
+}
+
+fn missing_value() -> Error {
+    Error::usage("missing value for flag")
+}
+
+fn invalid_value() -> Error {
+    Error::usage("invalid value for flag")
+}
 
-/// Prints the usage information for the program and exits with a non-zero status.
+/// Returns the usage information for the program, without a trailing newline.
 ///
 /// Displays valid command-line syntax and available options.
 ///
-/// Intended to be called when the user provides invalid input or provides the `-h` flag.
-pub fn print_usage(program: &str) {
-    println!("Usage:");
-    println!("      {program} [options] [infile [outfile]]");
-    println!("   or");
-    println!("      {program} -r [infile [outfile]]");
-    println!("Options:");
+/// Intended to be shown when the user provides invalid input or provides the `-h` flag.
+pub fn usage(program: &str) -> String {
+    let mut usage = format!(
+        "Usage:\n      {program} [options] [infile [outfile]]\n   or\n      {program} -m [options] file...\n   or\n      {program} -r [-p | -i | -b | -e] [infile [outfile]]\n   or\n      {program} -r -m [options] [infile [directory]]\nOptions:\n"
+    );
+
+    let names: Vec<String> = FLAG_REGISTRY
+        .iter()
+        .map(|flag| {
+            let short = match flag.short {
+                Some(short) => format!("-{short}, "),
+                None => String::from("    "),
+            };
 
-    for flag in FLAG_REGISTRY {
-        println!("   {}  {}", flag.name, flag.description);
+            match flag.value {
+                Some(value) => format!("{short}--{} <{value}>", flag.long),
+                None => format!("{short}--{}", flag.long),
+            }
+        })
+        .collect();
+
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+
+    for (name, flag) in names.iter().zip(FLAG_REGISTRY) {
+        usage.push_str(&format!("   {name:<width$}  {}\n", flag.description));
     }
 
-    process::exit(1);
+    usage.push_str("Use '-' for stdin or stdout, and '--' to end options.");
+    usage
 }
 
-/// Prints the program name and version, then exits successfully.
+/// Returns the program name and version.
 ///
 /// Uses the version specified in the crate metadata (`CARGO_PKG_VERSION`).
-pub fn print_version(program: &str) {
-    println!("{} - {}", program, env!("CARGO_PKG_VERSION"));
-    process::exit(0);
+pub fn version(program: &str) -> String {
+    format!("{} - {}", program, env!("CARGO_PKG_VERSION"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::test_util::test_path;
+
+    // Builds a `Config` to run, failing the test if help or version information is returned
+    fn build<T, A>(args: T) -> Result<Config, Error>
+    where
+        T: IntoIterator<Item = A>,
+        A: Into<OsString>,
+    {
+        match Config::build(args, "test")? {
+            Action::Run(config) => Ok(*config),
+            Action::Help(_) | Action::Version(_) => panic!("expected a configuration to run"),
+        }
+    }
+
     #[test]
     fn valid_flags() {
         let flags = vec![
@@ -226,7 +1218,7 @@ mod tests {
             String::from("3"),
         ];
 
-        let config = Config::build(flags.into_iter(), "test");
+        let config = build(flags);
 
         assert!(config.is_ok());
 
@@ -237,31 +1229,420 @@ mod tests {
 
     #[test]
     fn valid_without_flags() {
-        let flags = vec![];
-        let config = Config::build(flags.into_iter(), "test").unwrap();
+        let flags: Vec<String> = vec![];
+        let config = build(flags).unwrap();
 
         assert_eq!(config.cols, 16);
         assert_eq!(config.byte_groups, 2);
     }
 
+    #[test]
+    fn valid_plain_default_cols() {
+        let flags = vec![String::from("-p")];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.format, Format::Plain);
+        assert_eq!(config.cols, 30);
+    }
+
+    #[test]
+    fn valid_bits_defaults() {
+        let flags = vec![String::from("-b"), String::from("-c"), String::from("4")];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.format, Format::Bits);
+        assert_eq!(config.cols, 4);
+        assert_eq!(config.byte_groups, 1);
+    }
+
+    #[test]
+    fn valid_include_name() {
+        let flags = vec![
+            String::from("-i"),
+            String::from("-n"),
+            String::from("blob"),
+            String::from("-C"),
+        ];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.format, Format::Include);
+        assert_eq!(config.cols, 12);
+        assert_eq!(config.name.as_deref(), Some("blob"));
+        assert!(config.capitalize);
+    }
+
+    #[test]
+    fn valid_display_offset() {
+        let flags = vec![
+            String::from("-o"),
+            String::from("0x8000"),
+            String::from("-d"),
+        ];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.display_offset, 0x8000);
+        assert!(config.decimal);
+    }
+
+    #[test]
+    fn valid_offset_width() {
+        {
+            let config = build(["-w", "12"]).unwrap();
+            assert_eq!(config.offset_width, Some(12));
+        }
+
+        let config = build(["--offset-width=auto"]).unwrap();
+        assert_eq!(config.offset_width, None);
+    }
+
+    #[test]
+    fn invalid_offset_width() {
+        for width in ["0", "21", "wide"] {
+            assert!(build(["-w", width]).is_err());
+        }
+    }
+
+    #[test]
+    fn valid_color_choice() {
+        let flags = vec![String::from("-R"), String::from("always")];
+        let config = build(flags).unwrap();
+        assert!(config.color);
+    }
+
+    #[test]
+    fn invalid_color_choice() {
+        let flags = vec![String::from("-R"), String::from("sometimes")];
+        let result = build(flags);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn valid_charset_and_placeholder() {
+        let flags = vec![
+            String::from("-t"),
+            String::from("cp437"),
+            String::from("-P"),
+            String::from("·"),
+        ];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.charset, Charset::Cp437);
+        assert_eq!(config.placeholder, '·');
+    }
+
+    #[test]
+    fn valid_ebcdic() {
+        let flags = vec![String::from("-E")];
+        let config = build(flags).unwrap();
+        assert_eq!(config.charset, Charset::Ebcdic);
+    }
+
+    #[test]
+    fn valid_jobs() {
+        // Scoped, as a `Config` reading stdin holds its lock
+        {
+            let config = build(["-j", "4", "-a"]).unwrap();
+            assert_eq!(config.jobs, 4);
+            assert_eq!(
+                config.dumper().dump_bytes(&[0; 64]),
+                "00000000: 0000 0000 0000 0000 0000 0000 0000 0000  ................\n*\n00000030: 0000 0000 0000 0000 0000 0000 0000 0000  ................\n"
+            );
+        }
+
+        assert_eq!(build(Vec::<String>::new()).unwrap().jobs, 1);
+        assert!(build(["--jobs=0"]).is_err());
+    }
+
+    #[test]
+    fn valid_multi() {
+        let config = build(["-m", "--continuous", "Cargo.toml", "README.md"]).unwrap();
+
+        assert!(config.multi && config.continuous);
+        assert_eq!(
+            config.files,
+            [PathBuf::from("Cargo.toml"), PathBuf::from("README.md")]
+        );
+
+        // In reverse mode, the outfile is the directory the files are recreated in
+        let config = build(["-r", "-m", "Cargo.toml", "out"]).unwrap();
+
+        assert!(config.files.is_empty());
+        assert_eq!(config.directory, Some(PathBuf::from("out")));
+    }
+
+    #[test]
+    fn invalid_multi() {
+        for flags in [
+            &["-m"][..],
+            &["-m", "-"],
+            &["-m", "-s", "4", "Cargo.toml"],
+            &["-m", "-l", "4", "Cargo.toml"],
+        ] {
+            assert!(build(flags).is_err(), "{flags:?}");
+        }
+    }
+
+    #[test]
+    fn little_endian_byte_groups() {
+        for groups in ["1", "2", "8", "256"] {
+            assert!(build(["-e", "-g", groups]).is_ok(), "{groups}");
+        }
+
+        for flags in [
+            &["-e", "-g", "3"][..],
+            &["-g", "6", "-e"],
+            &["-r", "-e", "-g", "12"],
+        ] {
+            assert!(build(flags).is_err(), "{flags:?}");
+        }
+
+        // Other formats group bytes of any number
+        assert!(build(["-g", "3"]).is_ok());
+    }
+
+    #[test]
+    fn invalid_append_reverse() {
+        for flags in [&["-r", "--append"][..], &["-r", "-m", "--append", "-"]] {
+            assert!(build(flags).is_err(), "{flags:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_placeholder() {
+        let flags = vec![String::from("-P"), String::from("ab")];
+        let result = build(flags);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn invalid_missing_value() {
         let flags = vec![String::from("-c")];
-        let result = Config::build(flags.into_iter(), "test");
+        let result = build(flags);
         assert!(result.is_err());
     }
 
     #[test]
     fn invalid_flag_value() {
         let flags = vec![String::from("-c"), String::from("300")];
-        let result = Config::build(flags.into_iter(), "test");
+        let result = build(flags);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn valid_seek_and_len() {
+        let flags = vec![
+            String::from("-s"),
+            String::from("-0x10"),
+            String::from("-l"),
+            String::from("32"),
+        ];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.seek, Some(SeekFrom::End(-16)));
+        assert_eq!(config.len, Some(32));
+    }
+
+    #[test]
+    fn valid_relative_seek() {
+        let flags = vec![String::from("-s"), String::from("+8")];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.seek, Some(SeekFrom::Current(8)));
+    }
+
+    #[test]
+    fn invalid_seek_value() {
+        let flags = vec![String::from("-s"), String::from("ten")];
+        let result = build(flags);
+        assert!(result.is_err());
+    }
+
+    fn dump_to(input: &Path, output: &Path, extra: &[&str]) -> Result<(), Error> {
+        let mut args: Vec<OsString> = extra.iter().map(OsString::from).collect();
+        args.extend([input.into(), output.into()]);
+
+        crate::run(build(args)?)
+    }
+
+    #[test]
+    fn output_truncated_unless_appending() {
+        let (input, output) = (
+            test_path("config-truncate-in"),
+            test_path("config-truncate-out"),
+        );
+        fs::write(&input, b"hi").unwrap();
+        fs::write(&output, b"previous dump that is longer than the new one\n").unwrap();
+
+        let line = "00000000: 6869                                     hi\n";
+
+        dump_to(&input, &output, &[]).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), line);
+
+        dump_to(&input, &output, &["--append"]).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), line.repeat(2));
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_patched_in_place() {
+        let (input, output, link) = (
+            test_path("config-patch-in"),
+            test_path("config-patch-out"),
+            test_path("config-patch-link"),
+        );
+        let hard_link = test_path("config-patch-hard-link");
+        fs::write(&input, b"00000002: 4142  AB\n").unwrap();
+        fs::write(&output, b"abcdef").unwrap();
+
+        std::os::unix::fs::symlink(&output, &link).unwrap();
+        fs::hard_link(&output, &hard_link).unwrap();
+
+        // Written through the symbolic link, to the same inode
+        dump_to(&input, &link, &["-r"]).unwrap();
+        assert_eq!(fs::read(&hard_link).unwrap(), b"abABef");
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+
+        dump_to(&input, &link, &["--append"]).unwrap();
+        assert!(
+            fs::read_to_string(&hard_link)
+                .unwrap()
+                .starts_with("abABef00000000: ")
+        );
+
+        for path in [input, output, link, hard_link] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn seekable_files_and_devices() {
+        assert!(is_seekable(&File::open("Cargo.toml").unwrap()));
+
+        // Character devices may accept seeks, but only block devices hold data at offsets
+        #[cfg(unix)]
+        assert!(!is_seekable(&File::open("/dev/null").unwrap()));
+    }
+
+    #[test]
+    fn invalid_no_clobber_existing_output() {
+        let (input, output) = (
+            test_path("config-clobber-in"),
+            test_path("config-clobber-out"),
+        );
+        fs::write(&input, b"hi").unwrap();
+        fs::write(&output, b"keep").unwrap();
+
+        assert!(dump_to(&input, &output, &["--no-clobber"]).is_err());
+        assert_eq!(fs::read(&output).unwrap(), b"keep");
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+
+    #[test]
+    fn invalid_same_input_and_output() {
+        let input = test_path("config-same");
+        fs::write(&input, b"hi").unwrap();
+
+        assert!(dump_to(&input, &input, &[]).is_err());
+        assert_eq!(fs::read(&input).unwrap(), b"hi");
+
+        fs::remove_file(&input).unwrap();
+    }
+
+    #[test]
+    fn valid_long_options() {
+        let flags = vec![
+            String::from("--cols=10"),
+            String::from("--groupsize"),
+            String::from("3"),
+            String::from("--uppercase"),
+        ];
+        let config = build(flags).unwrap();
+
+        assert_eq!(config.cols, 10);
+        assert_eq!(config.byte_groups, 3);
+        assert!(config.uppercase);
+    }
+
+    #[test]
+    fn valid_clustered_flags_and_attached_values() {
+        let config = build(["-c10", "-ug3", "-s-16"]).unwrap();
+
+        assert_eq!(config.cols, 10);
+        assert!(config.uppercase);
+        assert_eq!(config.byte_groups, 3);
+        assert_eq!(config.seek, Some(SeekFrom::End(-16)));
+    }
+
+    #[test]
+    fn valid_dash_for_stdin_and_stdout() {
+        let config = build(["-", "-", "-p"]).unwrap();
+
+        assert_eq!(config.format, Format::Plain);
+        assert!(matches!(config.input, Input::Stream(_)));
+        assert!(matches!(config.output, Output::Stream(_)));
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let result = build(["--", "-c"]);
+        assert!(result.is_err_and(|err| err.to_string().starts_with("failed to open file")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = OsStr::from_bytes(b"hxx-missing-\xff");
+        let result = build([path]);
+        assert!(result.is_err_and(|err| err.to_string().starts_with("failed to open file")));
+    }
+
+    #[test]
+    fn invalid_value_for_switch() {
+        let result = build(["--plain=1"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn invalid_too_many_arguments() {
+        let result = build(["a", "b", "c"]);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn help_and_version_returned() {
+        let action = Config::build(["-c", "8", "--help", "-v"], "test").unwrap();
+        assert!(matches!(action, Action::Help(text) if text == usage("test")));
+
+        let action = Config::build(["-v"], "test").unwrap();
+        assert!(matches!(action, Action::Version(text) if text == version("test")));
+    }
+
+    #[test]
+    fn usage_lists_every_flag() {
+        let usage = usage("test");
+
+        for flag in FLAG_REGISTRY {
+            assert!(usage.contains(&format!("--{}", flag.long)));
+        }
+    }
+
     #[test]
     fn invalid_unknown_flag() {
         let flags = vec![String::from("-z")];
-        let result = Config::build(flags.into_iter(), "test");
+        let result = build(flags);
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,1320 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+
+use crate::charset::{Cell, char_width};
+use crate::color::Painter;
+use crate::{Charset, Error, Format, HexDecoder, HexEncoder, parallel};
+
+/// Hex dump formatter, independent of where bytes are read from and written to.
+///
+/// Options are set with builder methods, starting from the default `xxd` formatting.
+///
+/// # Example
+///
+/// ```
+/// let dumper = hxx::Dumper::new().cols(8).byte_groups(4).uppercase(true);
+///
+/// assert_eq!(
+///     dumper.dump_bytes(b"Hello, world!"),
+///     "00000000: 48656C6C 6F2C2077  Hello, w\n\
+///      00000008: 6F726C64 21        orld!\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dumper {
+    cols: Option<usize>,
+    byte_groups: Option<usize>,
+    format: Format,
+    autoskip: bool,
+    squeeze: bool,
+    uppercase: bool,
+    display_offset: u64,
+    decimal: bool,
+    offset_width: usize,
+    color: bool,
+    charset: Charset,
+    placeholder: char,
+    name: Option<String>,
+    capitalize: bool,
+    jobs: usize,
+}
+
+impl Dumper {
+    /// Creates a `Dumper` with the default `xxd` formatting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of bytes per line. Defaults to `Format::default_cols` of the format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cols` is 0.
+    pub fn cols(mut self, cols: usize) -> Self {
+        assert!(cols > 0, "a line must hold at least one byte");
+        self.cols = Some(cols);
+        self
+    }
+
+    /// Sets the number of bytes per group. Defaults to `Format::default_byte_groups` of the
+    /// format. Groups larger than a line are limited to the line, like `xxd`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_groups` is 0.
+    pub fn byte_groups(mut self, byte_groups: usize) -> Self {
+        assert!(byte_groups > 0, "a group must hold at least one byte");
+        self.byte_groups = Some(byte_groups);
+        self
+    }
+
+    /// Sets the layout of the hex dump.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// If `true`, runs of nul lines are replaced by a single `*` line, like `xxd`.
+    pub fn autoskip(mut self, autoskip: bool) -> Self {
+        self.autoskip = autoskip;
+        self
+    }
+
+    /// If `true`, runs of any repeated lines are replaced by a single `*` line, like `hexdump`.
+    pub fn squeeze(mut self, squeeze: bool) -> Self {
+        self.squeeze = squeeze;
+        self
+    }
+
+    /// If `true`, hex digits and offsets are shown in uppercase.
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Sets the value added to every displayed offset (wrapping like `xxd`).
+    pub fn display_offset(mut self, display_offset: u64) -> Self {
+        self.display_offset = display_offset;
+        self
+    }
+
+    /// If `true`, offsets are shown in decimal instead of hex.
+    pub fn decimal(mut self, decimal: bool) -> Self {
+        self.decimal = decimal;
+        self
+    }
+
+    /// Sets the minimum number of digits of the offsets, padded with zeros. Defaults to 8,
+    /// like `xxd`. Wider offsets are shown in full, which shifts the rest of their lines, so
+    /// the width should fit the last offset of the hex dump (see `offset_width_for`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset_width` is 0.
+    pub fn offset_width(mut self, offset_width: usize) -> Self {
+        assert!(offset_width > 0, "an offset must have at least one digit");
+        self.offset_width = offset_width;
+        self
+    }
+
+    /// Returns the number of digits needed to show every offset of a hex dump of `len` bytes
+    /// (with the display offset and `decimal` set), and at least 8 like `xxd`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let dumper = hxx::Dumper::new();
+    ///
+    /// assert_eq!(dumper.offset_width_for(0x1000), 8);
+    /// assert_eq!(dumper.offset_width_for(0x2_0000_0000), 9);
+    /// assert_eq!(dumper.display_offset(u64::MAX).offset_width_for(2), 16);
+    /// ```
+    pub fn offset_width_for(&self, len: u64) -> usize {
+        // Offsets wrapping past `u64::MAX` (like `xxd`) may take any value, up to the widest
+        let last = self.display_offset.saturating_add(len.saturating_sub(1));
+
+        let radix = if self.decimal { 10 } else { 16 };
+
+        (last.checked_ilog(radix).unwrap_or(0) as usize + 1).max(8)
+    }
+
+    /// If `true`, bytes are colored by class using ANSI escape sequences.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the character set used to show bytes in the text column.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Sets the character shown in the text column for bytes that are not printable.
+    pub fn placeholder(mut self, placeholder: char) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Sets the variable name used by `Format::Include`. Without a name, the array
+    /// declarations are omitted.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// If `true`, variable names used by `Format::Include` are capitalized.
+    pub fn capitalize(mut self, capitalize: bool) -> Self {
+        self.capitalize = capitalize;
+        self
+    }
+
+    /// Sets the number of threads formatting the lines of `Dumper::dump`. Input is still read,
+    /// and output written, in order on the calling thread, so the hex dump is the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jobs` is 0.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        assert!(jobs > 0, "lines must be formatted by at least one thread");
+        self.jobs = jobs;
+        self
+    }
+
+    /// Dumps everything read from `reader` to `writer`, with offsets starting at 0 (plus the
+    /// display offset).
+    ///
+    /// Input is read in large blocks, and lines are formatted into batches written in a single
+    /// call, so `writer` needs no buffering. It is flushed once the dump is complete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut output = Vec::new();
+    /// hxx::Dumper::new().dump(&b"hxx"[..], &mut output).unwrap();
+    ///
+    /// assert_eq!(output, b"00000000: 6878 78                                  hxx\n");
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if:
+    /// - It fails to read from `reader`.
+    /// - It fails to write to `writer`.
+    pub fn dump(&self, reader: impl Read, mut writer: impl Write) -> Result<(), Error> {
+        if self.jobs > 1 {
+            return parallel::dump(self, self.jobs, reader, writer);
+        }
+
+        let mut state = DumpState::new(self);
+        let cols = state.line_format.cols;
+        let context_len = state.line_format.charset.context_len();
+
+        let mut lines = LineReader::new(reader, cols, context_len);
+
+        state.start();
+
+        while lines
+            .read_line()
+            .map_err(Error::io("failed to read from input"))?
+            > 0
+        {
+            state.push(&mut writer, lines.bytes(), lines.context())?;
+        }
+
+        state.end(&mut writer)?;
+
+        writer
+            .flush()
+            .map_err(Error::io("failed to write to output"))?;
+
+        Ok(())
+    }
+
+    /// Returns a lazy iterator over the lines of the hex dump of `reader`, as `DumpLine`
+    /// records instead of text.
+    ///
+    /// Every line is yielded: `autoskip` and `squeeze` do not apply, and neither do colors.
+    /// Hex sections are laid out as for `Format::Normal`, unless the format is `Format::Bits`
+    /// or `Format::LittleEndian`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let dumper = hxx::Dumper::new().cols(4);
+    /// let mut lines = dumper.lines(&b"hello"[..]);
+    ///
+    /// let line = lines.next().unwrap().unwrap();
+    /// assert_eq!((line.offset, line.hex.as_str(), line.text.as_str()), (0, "6865 6c6c", "hell"));
+    ///
+    /// let line = lines.next().unwrap().unwrap();
+    /// assert_eq!((line.offset, line.bytes.as_slice()), (4, &b"o"[..]));
+    ///
+    /// assert!(lines.next().is_none());
+    /// ```
+    pub fn lines<R: Read>(&self, reader: R) -> DumpLines<R> {
+        let line_format = LineFormat::new(self);
+        let cols = line_format.cols;
+        let context_len = line_format.charset.context_len();
+
+        DumpLines {
+            lines: LineReader::new(reader, cols, context_len),
+            line: DumpLine {
+                offset: 0,
+                bytes: Vec::with_capacity(cols),
+                hex: String::with_capacity(cols << 3),
+                text: String::with_capacity(cols << 2),
+            },
+            line_format,
+        }
+    }
+
+    /// Returns an adapter hex dumping every byte written through it to `writer`, with this
+    /// formatting.
+    pub fn encoder<W: Write>(&self, writer: W) -> HexEncoder<W> {
+        HexEncoder::with_dumper(self, writer)
+    }
+
+    /// Returns an adapter reading the binary data of the hex dump read from `reader`, which is
+    /// expected to use this format (and `cols`, `byte_groups`, `decimal` and `display_offset`
+    /// where they matter, as in reverse mode).
+    pub fn decoder<R: BufRead>(&self, reader: R) -> HexDecoder<R> {
+        HexDecoder::with_dumper(self, reader)
+    }
+
+    /// Dumps `bytes` to a `String`, with offsets starting at 0 (plus the display offset).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let dumper = hxx::Dumper::new().format(hxx::Format::Plain);
+    ///
+    /// assert_eq!(dumper.dump_bytes(b"hxx"), "687878\n");
+    /// ```
+    pub fn dump_bytes(&self, bytes: &[u8]) -> String {
+        let mut output = Vec::with_capacity(bytes.len() * 5);
+
+        // Neither reading from a slice nor writing to a `Vec` can fail, and the output is
+        // only made of complete characters
+        self.dump(bytes, &mut output)
+            .expect("dumping to memory cannot fail");
+
+        String::from_utf8(output).expect("hex dumps are valid UTF-8")
+    }
+
+    /// Formats `bytes` as a single line of the hex dump at `offset` (the position of `bytes`
+    /// in the data, before the display offset is added), without its line terminator.
+    ///
+    /// Lines are never skipped, C include lines are formatted without separators from the
+    /// surrounding lines, and characters crossing the boundaries of the line are not decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let dumper = hxx::Dumper::new().cols(4);
+    ///
+    /// assert_eq!(dumper.format_line(b"hxx", 0x10), "00000010: 6878 78    hxx");
+    /// ```
+    pub fn format_line(&self, bytes: &[u8], offset: u64) -> String {
+        let line_format = LineFormat::new(self);
+        let mut line = Vec::with_capacity(bytes.len() << 3);
+
+        let context = LineContext {
+            index: offset,
+            ..Default::default()
+        };
+
+        line_format.write_line(&mut line, bytes, offset, context);
+
+        String::from_utf8(line).expect("hex dumps are valid UTF-8")
+    }
+}
+
+/// A line of a hex dump, as yielded by `DumpLines`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpLine {
+    /// Displayed offset of the line: its position in the data, plus the display offset.
+    pub offset: u64,
+
+    /// Bytes of the line, at most `cols`.
+    pub bytes: Vec<u8>,
+
+    /// Hex (or binary digit) representation of the bytes, grouped, without padding.
+    pub hex: String,
+
+    /// Text representation of the bytes in the charset, with placeholders.
+    pub text: String,
+}
+
+/// Lazy iterator over the lines of a hex dump, returned by `Dumper::lines`.
+///
+/// Input is read one line at a time through a buffered reader. Iterating yields owned
+/// `DumpLine`s; `DumpLines::next_line` instead reuses the same record for every line, without
+/// allocating.
+pub struct DumpLines<R> {
+    lines: LineReader<R>,
+    line: DumpLine,
+    line_format: LineFormat,
+}
+
+impl<R: Read> DumpLines<R> {
+    /// Reads the next line into a record reused across calls, returning `None` at the end of
+    /// input.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if it fails to read from the input.
+    pub fn next_line(&mut self) -> Option<Result<&DumpLine, Error>> {
+        match self.lines.read_line() {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => return Some(Err(Error::io("failed to read from input")(err))),
+        }
+
+        let bytes = self.lines.bytes();
+        let line = &mut self.line;
+
+        line.offset = self
+            .lines
+            .offset
+            .wrapping_add(self.line_format.display_offset);
+
+        line.bytes.clear();
+        line.bytes.extend_from_slice(bytes);
+        line.hex.clear();
+        line.text.clear();
+
+        // Records are meant to be rendered by the caller, so they are never colored
+        let mut painter = Painter::new(false, Charset::Ascii);
+
+        // Sections are formatted as bytes, reusing the allocations of the record
+        let mut hex = mem::take(&mut line.hex).into_bytes();
+        let mut text = mem::take(&mut line.text).into_bytes();
+
+        write_hex_section(&mut hex, bytes, &self.line_format, &mut painter);
+        write_text_section(
+            &mut text,
+            bytes,
+            self.lines.context(),
+            &self.line_format,
+            &mut painter,
+        );
+
+        line.hex = String::from_utf8(hex).expect("hex sections are valid UTF-8");
+        line.text = String::from_utf8(text).expect("text sections are valid UTF-8");
+
+        Some(Ok(&self.line))
+    }
+}
+
+impl<R: Read> Iterator for DumpLines<R> {
+    type Item = Result<DumpLine, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line().map(|line| line.cloned())
+    }
+}
+
+impl Default for Dumper {
+    /// Default `xxd` formatting.
+    fn default() -> Self {
+        Self {
+            cols: None,
+            byte_groups: None,
+            format: Format::Normal,
+            autoskip: false,
+            squeeze: false,
+            uppercase: false,
+            display_offset: 0,
+            decimal: false,
+            offset_width: 8,
+            color: false,
+            charset: Charset::Ascii,
+            placeholder: '.',
+            name: None,
+            capitalize: false,
+            jobs: 1,
+        }
+    }
+}
+
+// Reads into `buf` until it is full or the input ends, returning the number of bytes read.
+// Streams such as pipes or terminals may return fewer bytes than requested, which would
+// otherwise produce short lines in the middle of the dump; interrupted reads are retried
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(filled)
+}
+
+// Shown in the text column for the remaining bytes of multi-byte characters
+const CONTINUATION: char = '·';
+
+// Shown in the text column for the bytes of invalid sequences
+const INVALID: char = '�';
+
+// Position of a line in the dumped data, and the bytes around it (as many as needed by the
+// charset), used to decode characters crossing the line boundaries
+#[derive(Clone, Copy, Default)]
+pub(crate) struct LineContext<'a> {
+    pub(crate) index: u64,
+    pub(crate) before: &'a [u8],
+    pub(crate) after: &'a [u8],
+}
+
+pub(crate) fn head(bytes: &[u8], len: usize) -> &[u8] {
+    &bytes[..len.min(bytes.len())]
+}
+
+pub(crate) fn tail(bytes: &[u8], len: usize) -> &[u8] {
+    &bytes[bytes.len().saturating_sub(len)..]
+}
+
+// Keeps the last `len` bytes of `tail` followed by `bytes` in `tail`
+pub(crate) fn keep_tail(tail: &mut Vec<u8>, bytes: &[u8], len: usize) {
+    tail.extend_from_slice(bytes);
+    tail.drain(..tail.len().saturating_sub(len));
+}
+
+// Reads the input one line at a time into a reused buffer, along with the bytes before and
+// ahead of the line (`context_len` of each), to decode characters crossing its boundaries
+pub(crate) struct LineReader<R> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    len: usize,
+    // Position of the current line in the input
+    offset: u64,
+    context_len: usize,
+    before: Vec<u8>,
+    ahead: Vec<u8>,
+}
+
+impl<R: Read> LineReader<R> {
+    pub(crate) fn new(reader: R, cols: usize, context_len: usize) -> Self {
+        Self {
+            reader: BufReader::with_capacity(BLOCK_SIZE, reader),
+            buf: vec![0u8; cols],
+            len: 0,
+            offset: 0,
+            context_len,
+            before: Vec::with_capacity(context_len),
+            ahead: Vec::with_capacity(context_len),
+        }
+    }
+
+    // Moves to the next line, returning its length, or 0 at the end of input
+    pub(crate) fn read_line(&mut self) -> io::Result<usize> {
+        let cols = self.buf.len();
+        let context_len = self.context_len;
+
+        keep_tail(&mut self.before, &self.buf[..self.len], context_len);
+        self.offset += self.len as u64;
+        self.len = 0;
+
+        // Bytes read ahead start the next line (or lines, if there are fewer columns)
+        let carried = self.ahead.len().min(cols);
+        self.buf[..carried].copy_from_slice(&self.ahead[..carried]);
+        self.ahead.drain(..carried);
+
+        let len = carried + read_full(&mut self.reader, &mut self.buf[carried..])?;
+
+        if len > 0 && self.ahead.len() < context_len {
+            self.reader
+                .by_ref()
+                .take((context_len - self.ahead.len()) as u64)
+                .read_to_end(&mut self.ahead)?;
+        }
+
+        self.len = len;
+
+        Ok(len)
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub(crate) fn context(&self) -> LineContext<'_> {
+        LineContext {
+            index: self.offset,
+            before: &self.before,
+            after: &self.ahead,
+        }
+    }
+}
+
+// Size of the blocks read from the input, and of the batches of formatted lines written to the
+// output, so that large inputs take few system calls
+pub(crate) const BLOCK_SIZE: usize = 64 * 1024;
+
+// Hex dump in progress, formatting lines as they are pushed: runs of repeated lines are held
+// back until a different line (or the end) shows how many were skipped, and C include
+// declarations surround the lines.
+//
+// Lines are formatted into a batch, which is only written once it reaches `BLOCK_SIZE` or
+// when explicitly flushed
+pub(crate) struct DumpState {
+    line_format: LineFormat,
+    // C include declarations are only written when a variable name is available
+    name: Option<String>,
+    capitalize: bool,
+    // Only formats with offsets can show that lines were skipped
+    skip_repeats: bool,
+    squeeze: bool,
+    // Bytes of the previous line shown, and the number of identical lines skipped after it
+    prev: Vec<u8>,
+    skipped: u64,
+    // Number of bytes pushed so far
+    len: u64,
+    lines: Vec<u8>,
+}
+
+impl DumpState {
+    pub(crate) fn new(dumper: &Dumper) -> Self {
+        let line_format = LineFormat::new(dumper);
+        let cols = line_format.cols;
+
+        let name = match dumper.format {
+            Format::Include => dumper
+                .name
+                .as_deref()
+                .map(|name| c_identifier(name, dumper.capitalize)),
+            _ => None,
+        };
+
+        Self {
+            line_format,
+            name,
+            capitalize: dumper.capitalize,
+            skip_repeats: (dumper.autoskip || dumper.squeeze)
+                && matches!(
+                    dumper.format,
+                    Format::Normal | Format::Bits | Format::LittleEndian
+                ),
+            squeeze: dumper.squeeze,
+            prev: Vec::with_capacity(cols),
+            skipped: 0,
+            len: 0,
+            // Room for a full batch, plus the line that completes it
+            lines: Vec::with_capacity(BLOCK_SIZE + (cols << 5)),
+        }
+    }
+
+    pub(crate) fn line_format(&self) -> &LineFormat {
+        &self.line_format
+    }
+
+    // Formats what precedes the lines
+    pub(crate) fn start(&mut self) {
+        if let Some(name) = &self.name {
+            self.lines
+                .extend_from_slice(format!("unsigned char {name}[] = {{\n").as_bytes());
+        }
+    }
+
+    // Formats the line `bytes`, found at `context.index` in the data
+    pub(crate) fn push(
+        &mut self,
+        writer: &mut impl Write,
+        bytes: &[u8],
+        context: LineContext,
+    ) -> Result<(), Error> {
+        let offset = context.index;
+
+        self.len += bytes.len() as u64;
+
+        if self.skip_repeats {
+            if self.is_repeat(&self.prev, bytes) {
+                self.skipped += 1;
+                return Ok(());
+            }
+
+            self.close_run(offset, bytes);
+
+            self.prev.clear();
+            self.prev.extend_from_slice(bytes);
+        }
+
+        self.line_format
+            .format_line(&mut self.lines, bytes, offset, context);
+
+        if self.lines.len() >= BLOCK_SIZE {
+            self.flush(writer)?;
+        }
+
+        Ok(())
+    }
+
+    // Continues a dump whose first `len` bytes are formatted separately
+    pub(crate) fn resume(&mut self, len: u64) {
+        self.len = len;
+    }
+
+    // Whether the line `bytes` is skipped after the line `prev`. Autoskip only applies to nul
+    // lines, while squeezing applies to any line
+    pub(crate) fn is_repeat(&self, prev: &[u8], bytes: &[u8]) -> bool {
+        self.skip_repeats && bytes == prev && (self.squeeze || bytes.iter().all(|&b| b == 0))
+    }
+
+    // Skips `count` more lines identical to the previous one, without comparing them
+    pub(crate) fn repeat(&mut self, count: u64) {
+        self.skipped += count;
+        self.len += count * self.prev.len() as u64;
+    }
+
+    // Formats the run of lines skipped before the line `next`, found at `offset`
+    pub(crate) fn close_run(&mut self, offset: u64, next: &[u8]) {
+        if self.skipped == 0 {
+            return;
+        }
+
+        let prev = &self.prev;
+        let context_len = self.line_format.charset.context_len();
+        let skipped_offset = offset - self.skipped * prev.len() as u64;
+
+        let context = LineContext {
+            index: skipped_offset,
+            // Skipped lines are preceded by a line identical to them
+            before: tail(prev, context_len),
+            after: head(next, context_len),
+        };
+
+        self.line_format.format_skipped(
+            &mut self.lines,
+            prev,
+            skipped_offset,
+            self.skipped,
+            context,
+        );
+        self.skipped = 0;
+    }
+
+    // Formats the lines held back and what follows the lines, and writes every line left
+    pub(crate) fn end(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        let offset = self.len;
+        let context_len = self.line_format.charset.context_len();
+
+        // The last line is always shown, so the end of the input is visible
+        if self.skipped > 0 {
+            let prev = &self.prev;
+            let last_offset = offset - prev.len() as u64;
+            let skipped_offset = last_offset - (self.skipped - 1) * prev.len() as u64;
+
+            let context = LineContext {
+                index: skipped_offset,
+                before: tail(prev, context_len),
+                after: head(prev, context_len),
+            };
+
+            self.line_format.format_skipped(
+                &mut self.lines,
+                prev,
+                skipped_offset,
+                self.skipped - 1,
+                context,
+            );
+
+            let context = LineContext {
+                index: last_offset,
+                before: tail(prev, context_len),
+                after: &[],
+            };
+
+            self.line_format
+                .format_line(&mut self.lines, prev, last_offset, context);
+
+            self.skipped = 0;
+        }
+
+        if self.line_format.format == Format::Include {
+            if offset != 0 {
+                self.lines.push(b'\n');
+            }
+
+            if let Some(name) = &self.name {
+                let len_suffix = if self.capitalize { "LEN" } else { "len" };
+                let declaration = format!("}};\nunsigned int {name}_{len_suffix} = {offset};\n");
+
+                self.lines.extend_from_slice(declaration.as_bytes());
+            }
+        }
+
+        self.flush(writer)
+    }
+
+    // Writes the lines formatted so far
+    pub(crate) fn flush(&mut self, writer: &mut impl Write) -> Result<(), Error> {
+        writer
+            .write_all(&self.lines)
+            .map_err(Error::io("failed to write to output"))?;
+
+        // Reset buffer before formatting again to avoid extra allocations
+        self.lines.clear();
+
+        Ok(())
+    }
+}
+
+// Formatting options shared by every line of a hex dump, with defaults resolved
+pub(crate) struct LineFormat {
+    pub(crate) cols: usize,
+    pub(crate) byte_groups: usize,
+    pub(crate) format: Format,
+    pub(crate) uppercase: bool,
+    pub(crate) display_offset: u64,
+    pub(crate) decimal: bool,
+    pub(crate) offset_width: usize,
+    pub(crate) color: bool,
+    pub(crate) charset: Charset,
+    pub(crate) placeholder: char,
+    // Hex digits of every byte, in the case used
+    hex: &'static [[u8; 2]; 256],
+    // Text column cell of every byte, for charsets where it does not depend on other bytes
+    text: Option<Box<TextTable>>,
+}
+
+impl LineFormat {
+    pub(crate) fn new(dumper: &Dumper) -> Self {
+        let format = dumper.format;
+        let cols = dumper.cols.unwrap_or(format.default_cols());
+        let byte_groups = dumper.byte_groups.unwrap_or(format.default_byte_groups());
+
+        Self {
+            cols,
+            // Groups larger than a line are limited to the line, matching `xxd`
+            byte_groups: byte_groups.min(cols),
+            format,
+            uppercase: dumper.uppercase,
+            display_offset: dumper.display_offset,
+            decimal: dumper.decimal,
+            offset_width: dumper.offset_width,
+            color: dumper.color,
+            charset: dumper.charset,
+            placeholder: dumper.placeholder,
+            hex: if dumper.uppercase {
+                &HEX_UPPER
+            } else {
+                &HEX_LOWER
+            },
+            text: TextTable::new(dumper.charset, dumper.placeholder),
+        }
+    }
+
+    // Formats a line of the hex dump, including its separator from the previous line or its
+    // line terminator
+    fn format_line(&self, line: &mut Vec<u8>, buffer: &[u8], offset: u64, context: LineContext) {
+        // C include lines are terminated once it is known whether more elements follow
+        if self.format == Format::Include && context.index != 0 {
+            line.extend_from_slice(b",\n");
+        }
+
+        self.write_line(line, buffer, offset, context);
+
+        if self.format != Format::Include {
+            line.push(b'\n');
+        }
+    }
+
+    // Formats a line of the hex dump on its own
+    fn write_line(&self, line: &mut Vec<u8>, buffer: &[u8], offset: u64, context: LineContext) {
+        match self.format {
+            Format::Normal | Format::Bits | Format::LittleEndian => {
+                format_hex_dump_line(line, buffer, offset, context, self)
+            }
+            Format::Plain => format_plain_hex_dump_line(line, buffer, self),
+            Format::Include => format_include_hex_dump_line(line, buffer, self),
+        }
+    }
+
+    // Formats a run of `skipped` lines identical to `buffer`, starting at `offset`. Like
+    // `xxd`, a single line is shown as is, since `*` would take up as much space
+    fn format_skipped(
+        &self,
+        line: &mut Vec<u8>,
+        buffer: &[u8],
+        offset: u64,
+        skipped: u64,
+        context: LineContext,
+    ) {
+        match skipped {
+            0 => {}
+            1 => self.format_line(line, buffer, offset, context),
+            _ => line.extend_from_slice(b"*\n"),
+        }
+    }
+}
+
+// Hex digits of every byte, so that bytes are formatted by table lookups rather than `fmt`
+static HEX_LOWER: [[u8; 2]; 256] = hex_table(b"0123456789abcdef");
+static HEX_UPPER: [[u8; 2]; 256] = hex_table(b"0123456789ABCDEF");
+
+// Binary digits of every byte, most significant first
+static BITS: [[u8; 8]; 256] = bits_table();
+
+const fn hex_table(digits: &[u8; 16]) -> [[u8; 2]; 256] {
+    let mut table = [[0; 2]; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        table[byte] = [digits[byte >> 4], digits[byte & 0xf]];
+        byte += 1;
+    }
+
+    table
+}
+
+const fn bits_table() -> [[u8; 8]; 256] {
+    let mut table = [[0; 8]; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        let mut bit = 0;
+
+        while bit < 8 {
+            table[byte][bit] = b'0' + ((byte >> (7 - bit)) & 1) as u8;
+            bit += 1;
+        }
+
+        byte += 1;
+    }
+
+    table
+}
+
+// UTF-8 encoding of the text column cell of every byte
+struct TextTable {
+    cells: [[u8; 4]; 256],
+    lens: [u8; 256],
+}
+
+impl TextTable {
+    // Only charsets decoding every byte on its own into a narrow character (or none) can be
+    // tabulated; others are decoded line by line
+    fn new(charset: Charset, placeholder: char) -> Option<Box<Self>> {
+        if charset.context_len() > 0 {
+            return None;
+        }
+
+        let mut table = Box::new(Self {
+            cells: [[0; 4]; 256],
+            lens: [0; 256],
+        });
+
+        for byte in 0..=255 {
+            let c = match charset.decode(byte) {
+                Some(c) if char_width(c) == 1 => c,
+                Some(_) => return None,
+                None => placeholder,
+            };
+
+            let len = c.encode_utf8(&mut table.cells[byte as usize]).len();
+            table.lens[byte as usize] = len as u8;
+        }
+
+        Some(table)
+    }
+
+    fn write_cell(&self, line: &mut Vec<u8>, byte: u8) {
+        let cell = &self.cells[byte as usize];
+
+        // Most cells are a single byte, which is cheaper to push than to copy as a slice
+        match self.lens[byte as usize] {
+            1 => line.push(cell[0]),
+            len => line.extend_from_slice(&cell[..len as usize]),
+        }
+    }
+}
+
+fn format_hex_dump_line(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    offset: u64,
+    context: LineContext,
+    line_format: &LineFormat,
+) {
+    let LineFormat {
+        cols,
+        byte_groups,
+        format,
+        display_offset,
+        decimal,
+        offset_width,
+        color,
+        charset,
+        ..
+    } = *line_format;
+
+    let bytes_read = buffer.len();
+    let mut painter = Painter::new(color, charset);
+
+    // Position in the data being processed, shifted by the display offset (wrapping like `xxd`)
+    let offset = offset.wrapping_add(display_offset);
+
+    write_offset(line, offset, decimal, offset_width, line_format.hex);
+
+    write_hex_section(line, buffer, line_format, &mut painter);
+
+    if bytes_read < cols {
+        // Number of digits used to represent each byte
+        let digits = match format {
+            Format::Bits => 8,
+            _ => 2,
+        };
+
+        // padding = width of a full line - width written, where the width is the digits of
+        // each byte + a space between each byte group (color escapes take up no width)
+        let width = |bytes: usize| match format {
+            // Every group takes up the width of a full group
+            Format::LittleEndian => bytes.div_ceil(byte_groups) * (byte_groups * digits + 1) - 1,
+            _ => bytes * digits + bytes.div_ceil(byte_groups) - 1,
+        };
+        let padding = width(cols) - width(bytes_read);
+
+        // Add padding to align the remaining ASCII representation
+        line.resize(line.len() + padding, b' ');
+    }
+
+    // To match `xxd` formatting
+    line.extend_from_slice(b"  ");
+
+    write_text_section(line, buffer, context, line_format, &mut painter);
+}
+
+// Writes `offset` with at least `width` digits, followed by the separator
+fn write_offset(
+    line: &mut Vec<u8>,
+    offset: u64,
+    decimal: bool,
+    width: usize,
+    hex: &[[u8; 2]; 256],
+) {
+    // Enough for the 20 decimal digits of `u64::MAX`
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    let mut rest = offset;
+
+    let radix = if decimal { 10 } else { 16 };
+
+    loop {
+        start -= 1;
+        // The second digit of the bytes below 16 is the digit of their value
+        digits[start] = hex[(rest % radix) as usize][1];
+        rest /= radix;
+
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let len = digits.len() - start;
+
+    line.resize(line.len() + width.saturating_sub(len), b'0');
+    line.extend_from_slice(&digits[start..]);
+    line.extend_from_slice(b": ");
+}
+
+// Writes the bytes of `buffer` in hex (or binary digits), grouped as specified, without padding
+fn write_hex_section(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    line_format: &LineFormat,
+    painter: &mut Painter,
+) {
+    let LineFormat {
+        byte_groups,
+        format,
+        hex,
+        ..
+    } = *line_format;
+
+    for (i, group) in buffer.chunks(byte_groups).enumerate() {
+        // Insert space between byte groups
+        if i != 0 {
+            line.push(b' ');
+        }
+
+        match format {
+            Format::LittleEndian => {
+                // Partial groups are right-aligned within the width of a full group
+                let padding = (byte_groups - group.len()) * 2;
+                line.resize(line.len() + padding, b' ');
+
+                // Bytes of each group are shown as a little-endian word
+                for &byte in group.iter().rev() {
+                    painter.paint(line, byte);
+                    line.extend_from_slice(&hex[byte as usize]);
+                }
+            }
+            Format::Bits => {
+                for &byte in group {
+                    painter.paint(line, byte);
+                    line.extend_from_slice(&BITS[byte as usize]);
+                }
+            }
+            _ => {
+                for &byte in group {
+                    painter.paint(line, byte);
+                    line.extend_from_slice(&hex[byte as usize]);
+                }
+            }
+        }
+    }
+
+    painter.reset(line);
+}
+
+// Writes the bytes of `buffer` as characters of the charset, or placeholders if not printable
+fn write_text_section(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    context: LineContext,
+    line_format: &LineFormat,
+    painter: &mut Painter,
+) {
+    let LineFormat {
+        charset,
+        placeholder,
+        ..
+    } = *line_format;
+
+    if let Some(table) = &line_format.text {
+        for &b in buffer {
+            painter.paint(line, b);
+            table.write_cell(line, b);
+        }
+
+        painter.reset(line);
+        return;
+    }
+
+    let cells = charset.decode_line(buffer, context.index, context.before, context.after);
+    let mut cells = buffer.iter().zip(cells);
+
+    while let Some((&b, cell)) = cells.next() {
+        painter.paint(line, b);
+
+        let c = match cell {
+            Cell::Char(c) if char_width(c) == 1 => c,
+            // Wide characters cover the cell of the next byte, always part of the character; the
+            // text column ends the line, so one starting in the last cell may overflow it
+            Cell::Char(c) => {
+                cells.next();
+                c
+            }
+            Cell::Unprintable => placeholder,
+            Cell::Continuation => CONTINUATION,
+            Cell::Invalid => INVALID,
+        };
+
+        line.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    painter.reset(line);
+}
+
+fn format_plain_hex_dump_line(line: &mut Vec<u8>, buffer: &[u8], line_format: &LineFormat) {
+    let mut painter = Painter::new(line_format.color, line_format.charset);
+
+    for &byte in buffer {
+        painter.paint(line, byte);
+        line.extend_from_slice(&line_format.hex[byte as usize]);
+    }
+
+    painter.reset(line);
+}
+
+fn format_include_hex_dump_line(line: &mut Vec<u8>, buffer: &[u8], line_format: &LineFormat) {
+    line.push(b' ');
+
+    // Like `xxd`, the prefix follows the case of the hex digits
+    let prefix = if line_format.uppercase {
+        b" 0X"
+    } else {
+        b" 0x"
+    };
+
+    for &byte in buffer {
+        line.extend_from_slice(prefix);
+        line.extend_from_slice(&line_format.hex[byte as usize]);
+        line.push(b',');
+    }
+
+    // The separator of the last element on the line is written with the next line
+    line.pop();
+}
+
+// Derives a C identifier from `name` the same way `xxd` does: every byte that is not
+// alphanumeric becomes `_`, and a leading digit is prefixed with `__`
+fn c_identifier(name: &str, capitalize: bool) -> String {
+    let mut identifier = String::with_capacity(name.len() + 2);
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.push_str("__");
+    }
+
+    identifier.extend(name.bytes().map(|b| match b {
+        b if b.is_ascii_alphanumeric() && capitalize => b.to_ascii_uppercase() as char,
+        b if b.is_ascii_alphanumeric() => b as char,
+        _ => '_',
+    }));
+
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_defaults() {
+        let dumper = Dumper::new().format(Format::Plain);
+        let bytes = [0xab; 31];
+
+        assert_eq!(
+            dumper.dump_bytes(&bytes),
+            format!("{}\nab\n", "ab".repeat(30))
+        );
+
+        // Explicit options are kept, whatever the order they are set in
+        let dumper = Dumper::new().cols(2).format(Format::Plain);
+
+        assert_eq!(dumper.dump_bytes(b"hxx"), "6878\n78\n");
+    }
+
+    #[test]
+    fn test_dump_bytes_autoskip() {
+        let dumper = Dumper::new().cols(4).autoskip(true);
+        let mut bytes = vec![0; 16];
+        bytes.push(1);
+
+        assert_eq!(
+            dumper.dump_bytes(&bytes),
+            "00000000: 0000 0000  ....\n\
+             *\n\
+             00000010: 01         .\n"
+        );
+    }
+
+    #[test]
+    fn test_format_line() {
+        let dumper = Dumper::new().display_offset(0x100).uppercase(true);
+
+        assert_eq!(
+            dumper.format_line(b"\xff\x00", 0x20),
+            format!("00000120: FF00{:37}..", "")
+        );
+
+        // C include lines have no separators from the surrounding lines
+        let dumper = Dumper::new().format(Format::Include).name("blob");
+
+        assert_eq!(dumper.format_line(b"hx", 12), "  0x68, 0x78");
+    }
+
+    #[test]
+    fn test_offset_width() {
+        let dumper = Dumper::new().cols(4).offset_width(12);
+        assert_eq!(
+            dumper.format_line(b"hxx", 0x10),
+            "000000000010: 6878 78    hxx"
+        );
+
+        // Wider offsets are never truncated
+        let dumper = dumper.offset_width(2);
+        assert_eq!(dumper.format_line(b"hxx", 0x100), "100: 6878 78    hxx");
+
+        let dumper = Dumper::new().decimal(true).display_offset(99_999_999);
+        assert_eq!(dumper.offset_width_for(1), 8);
+        assert_eq!(dumper.offset_width_for(2), 9);
+    }
+
+    #[test]
+    fn test_format_tables() {
+        // Offsets grow past 8 digits, up to the full range of `u64`
+        for (offset, decimal, uppercase) in [
+            (0xabc_def0_1234, false, false),
+            (u64::MAX, false, true),
+            (u64::MAX, true, false),
+            (7, true, true),
+        ] {
+            let dumper = Dumper::new().decimal(decimal).uppercase(uppercase);
+            let line = dumper.format_line(b"x", offset);
+
+            let expected = match (decimal, uppercase) {
+                (true, _) => format!("{offset:08}: "),
+                (false, true) => format!("{offset:08X}: "),
+                (false, false) => format!("{offset:08x}: "),
+            };
+
+            assert!(line.starts_with(&expected), "{line:?}");
+        }
+
+        let dumper = Dumper::new().format(Format::Bits).cols(2);
+
+        for byte in 0..=255 {
+            let line = dumper.format_line(&[byte, !byte], 0);
+            assert!(line.contains(&format!("{byte:08b} {:08b}", !byte)));
+        }
+    }
+
+    #[test]
+    fn test_dump_to_writer() {
+        let dumper = Dumper::new().format(Format::Include).name("1 blob");
+        let mut output = Vec::new();
+
+        dumper.dump(&b"hx"[..], &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "unsigned char __1_blob[] = {\n  0x68, 0x78\n};\nunsigned int __1_blob_len = 2;\n"
+        );
+    }
+
+    #[test]
+    fn test_lines_decode_across_boundaries() {
+        let dumper = Dumper::new()
+            .cols(3)
+            .charset(Charset::Utf8)
+            .display_offset(0x10);
+
+        let lines: Vec<_> = dumper.lines("aé€".as_bytes()).map(Result::unwrap).collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].offset, 0x10);
+        assert_eq!(lines[0].hex, "61c3 a9");
+        assert_eq!(lines[0].text, "aé·");
+        assert_eq!(lines[1].offset, 0x13);
+        assert_eq!(lines[1].bytes, "€".as_bytes());
+        assert_eq!(lines[1].text, "€··");
+    }
+
+    #[test]
+    fn test_lines_match_dump() {
+        let dumper = Dumper::new().format(Format::LittleEndian).cols(6);
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        let mut dump = String::new();
+        let mut lines = dumper.lines(&bytes[..]);
+
+        // Records are reused, but hold the same sections as the text output
+        while let Some(line) = lines.next_line() {
+            let line = line.unwrap();
+            let padding = 17 - line.hex.len();
+
+            dump.push_str(&format!(
+                "{:08x}: {}{:padding$}  {}\n",
+                line.offset, line.hex, "", line.text
+            ));
+        }
+
+        assert_eq!(dump, dumper.dump_bytes(&bytes));
+    }
+}
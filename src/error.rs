@@ -0,0 +1,144 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::iter;
+
+use crate::charset::char_width;
+
+/// Error returned by command-line parsing, hex dumping and reverse hex dumping.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O operation failed, such as opening, reading, writing or seeking a file.
+    Io {
+        /// Operation that failed (e.g., "failed to read from input").
+        context: &'static str,
+
+        /// Underlying I/O error.
+        source: io::Error,
+    },
+
+    /// The command-line arguments or configuration are invalid.
+    Usage(String),
+
+    /// The hex dump given in reverse mode is malformed.
+    Parse {
+        /// Line of the input at fault, starting at 1.
+        line: usize,
+
+        /// Column (in characters) of the line at fault, starting at 1.
+        column: usize,
+
+        /// Contents of the line at fault, without its line terminator.
+        text: String,
+
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl Error {
+    // Wraps an I/O error with the operation that failed, for use with `map_err`
+    pub(crate) fn io(context: &'static str) -> impl FnOnce(io::Error) -> Self {
+        move |source| Self::Io { context, source }
+    }
+
+    pub(crate) fn usage(message: impl Into<String>) -> Self {
+        Self::Usage(message.into())
+    }
+
+    // Locates a problem found at the byte `index` of `text`, the `line`-th line of the input.
+    // Lines are parsed as raw bytes, so invalid UTF-8 is replaced when shown
+    pub(crate) fn parse(line: usize, text: &[u8], index: usize, message: &str) -> Self {
+        let end = text
+            .iter()
+            .rposition(|&b| b != b'\r' && b != b'\n')
+            .map_or(0, |end| end + 1);
+        let text = &text[..end];
+        let index = index.min(text.len());
+
+        let column = String::from_utf8_lossy(&text[..index]).chars().count() + 1;
+
+        Self::Parse {
+            line,
+            column,
+            text: String::from_utf8_lossy(text).into_owned(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Error {
+    /// Returns a caret (`^`) padded to fall under the column at fault, once printed below the
+    /// text of a `Parse` error, or `None` for other errors.
+    ///
+    /// Tabs in the text are kept in the padding, and wide or zero-width characters take up the
+    /// columns they do in a terminal, so the caret stays aligned whatever the line holds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let err = hxx::Error::Parse {
+    ///     line: 1,
+    ///     column: 7,
+    ///     text: "0:\t世界 zz".to_string(),
+    ///     message: "invalid hex digit".to_string(),
+    /// };
+    ///
+    /// assert_eq!(err.caret().as_deref(), Some("  \t     ^"));
+    /// ```
+    pub fn caret(&self) -> Option<String> {
+        let Self::Parse { column, text, .. } = self else {
+            return None;
+        };
+
+        let mut caret = String::new();
+
+        for c in text.chars().take(column.saturating_sub(1)) {
+            match c {
+                '\t' => caret.push('\t'),
+                c => caret.extend(iter::repeat_n(' ', char_width(c))),
+            }
+        }
+
+        caret.push('^');
+        Some(caret)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { context, source } => write!(f, "{context}: {source}"),
+            Self::Usage(message) => write!(f, "{message}"),
+            Self::Parse {
+                line,
+                column,
+                message,
+                ..
+            } => write!(f, "{message} (line {line}, column {column})"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Adapters implementing `Read` or `Write` can only report an `io::Error`, which keeps the
+// original error as its source
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match &err {
+            Error::Io { source, .. } => source.kind(),
+            Error::Usage(_) => io::ErrorKind::InvalidInput,
+            Error::Parse { .. } => io::ErrorKind::InvalidData,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
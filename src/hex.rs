@@ -1,15 +1,17 @@
 #[cfg(test)]
 use std::io::Cursor;
 
-use std::fmt::Write as _;
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, SeekFrom, Write};
 
-use crate::Config;
+use crate::dumper::{BLOCK_SIZE, LineFormat};
+use crate::{
+    Config, Dumper, Error, Format, Output, hex_dump_files, parallel, reverse_hex_dump_files,
+};
 
 /// Performs the appropriate operation, depending on the provided `Config`.
 ///
 /// Depending on the value of `reverse`, this function will perform either a
-/// hex dump or reverse hex dump.
+/// hex dump or reverse hex dump, of several files if `multi` is set.
 ///
 /// # Examples
 ///
@@ -18,8 +20,7 @@ use crate::Config;
 ///     cols: 16,
 ///     byte_groups: 2,
 ///     reverse: false,
-///     input: Box::new(std::io::stdin()),
-///     output: Box::new(std::io::stdout()),
+///     ..Default::default()
 /// };
 ///
 /// // Performs a hex dump
@@ -34,8 +35,7 @@ use crate::Config;
 ///     cols: 16,
 ///     byte_groups: 2,
 ///     reverse: true,
-///     input: Box::new(std::io::stdin()),
-///     output: Box::new(std::io::stdout()),
+///     ..Default::default()
 /// };
 ///
 /// // Performs a reverse hex dump
@@ -47,14 +47,16 @@ use crate::Config;
 ///
 /// # Error
 ///
-/// This function returns an error if the underlying `hex_dump` or `reverse_hex_dump`
-/// function fails. The specific error conditions are documented in the respective
-/// functions.
-pub fn run(config: Config) -> Result<(), String> {
-    match config.reverse {
-        true => {
+/// This function returns an error if the underlying `hex_dump`, `reverse_hex_dump`,
+/// `hex_dump_files` or `reverse_hex_dump_files` function fails. The specific error
+/// conditions are documented in the respective functions.
+pub fn run(config: Config) -> Result<(), Error> {
+    match (config.reverse, config.multi) {
+        (true, true) => reverse_hex_dump_files(config)?,
+        (true, false) => {
             reverse_hex_dump(config)?;
         }
+        (false, true) => hex_dump_files(config)?,
         _ => hex_dump(config)?,
     }
 
@@ -64,12 +66,20 @@ pub fn run(config: Config) -> Result<(), String> {
 /// Processes input on a single thread and generates a hex dump using the provided `Config`.
 ///
 /// Reads bytes from the configured input stream, formats each line with:
-/// - an 8-digit hexadecimal offset,
+/// - a hexadecimal offset of at least `offset_width` digits (8 by default),
 /// - the hex representation of bytes grouped as specified,
 /// - an ASCII representation of those bytes (`.` for non-printable characters),
-/// matching the style of the `xxd`.
 ///
-/// Lines are written to the configured output stream.
+/// matching the style of the `xxd`. Other layouts (plain hex, C include, binary digits) are
+/// selected with `format`.
+///
+/// If `seek` is set, dumping starts at that position and displayed offsets reflect the
+/// real position in the input. Seekable inputs are repositioned directly, while streams
+/// have the skipped bytes read and discarded. If `len` is set, dumping stops after that
+/// many bytes.
+///
+/// Lines are written to the configured output stream. Formatting is done by the `Dumper`
+/// returned by `Config::dumper`.
 ///
 /// # Example
 ///
@@ -78,8 +88,7 @@ pub fn run(config: Config) -> Result<(), String> {
 ///     cols: 16,
 ///     byte_groups: 2,
 ///     reverse: false,
-///     input: Box::new(std::io::stdin()),
-///     output: Box::new(std::io::stdout()),
+///     ..Default::default()
 /// };
 ///
 /// if let Err(err) = hxx::hex_dump(config) {
@@ -91,84 +100,49 @@ pub fn run(config: Config) -> Result<(), String> {
 /// # Error
 ///
 /// This function returns an error if:
+/// - It fails to seek to the requested position in the input stream.
 /// - It fails to read from the input stream.
 /// - It fails to write to the output stream.
 /// - An internal formatting or I/O operation encounters a failure.
-pub fn hex_dump(config: Config) -> Result<(), String> {
-    // Buffer I/O to minimize syscall overhead
-    let mut reader = BufReader::new(config.input);
-    let mut writer = BufWriter::new(config.output);
-
-    let cols = config.cols;
-    let byte_groups = config.byte_groups;
-
-    // Preallocate line buffer sized for a full read chunk
-    let mut line = String::with_capacity(cols << 3);
-
-    let mut buf = vec![0u8; cols];
-    let mut offset: usize = 0;
-
-    loop {
-        let bytes_read = reader
-            .read(&mut buf)
-            .map_err(|err| format!("failed to read from input: {err}"))?;
-
-        // Check for EOF
-        if bytes_read == 0 {
-            break;
-        }
-
-        format_hex_dump_line(&mut line, &buf[..bytes_read], offset, cols, byte_groups)?;
-
-        writeln!(writer, "{line}").map_err(|err| format!("failed to write to output: {err}"))?;
-        offset += bytes_read;
+pub fn hex_dump(config: Config) -> Result<(), Error> {
+    let dumper = config.dumper();
 
-        // Reset buffer before reading again to avoid extra allocations
-        line.clear();
-    }
+    let mut input = config.input;
+    let mut output = config.output;
 
-    Ok(())
-}
+    // Displayed offsets start from the real position in the input
+    let position = match config.seek {
+        Some(pos) => input.seek(pos).map_err(Error::io("failed to seek input"))?,
+        None => 0,
+    };
 
-fn format_hex_dump_line(
-    line: &mut String,
-    buffer: &[u8],
-    offset: usize,
-    cols: usize,
-    byte_groups: usize,
-) -> Result<(), String> {
-    let bytes_read = buffer.len();
+    let dumper = dumper.display_offset(config.display_offset.wrapping_add(position));
+    let len = config.len.unwrap_or(u64::MAX);
 
-    // Position in the data being processed
-    write!(line, "{:08x}: ", offset).map_err(|err| format!("failed to write to line: {err}"))?;
+    // `auto` fits the last offset shown, unless the size of the input is unknown
+    let remaining = match config.offset_width {
+        None => input
+            .remaining()
+            .map_err(Error::io("failed to seek input"))?,
+        Some(_) => None,
+    };
 
-    for (i, byte) in buffer.iter().enumerate() {
-        // Insert space after the first byte and if a byte group has been written
-        if i != 0 && i % byte_groups == 0 {
-            line.push(' ');
+    let dumper = match remaining {
+        Some(remaining) => {
+            let offset_width = dumper.offset_width_for(remaining.min(len));
+            dumper.offset_width(offset_width)
         }
+        None => dumper,
+    };
 
-        write!(line, "{:02x}", *byte).map_err(|err| format!("failed to write to line: {err}"))?;
-    }
-
-    if bytes_read < cols {
-        // padding = remaining bytes * 2 for hex-width + spaces between byte groups
-        let padding = (cols - bytes_read) * 2 + ((cols - bytes_read) / byte_groups);
-
-        // Add padding to align the remaining ASCII representation
-        write!(line, "{:>padding$}", "")
-            .map_err(|err| format!("failed to write to line: {err}"))?;
-    }
+    let input = input.take(len);
 
-    // To match `xxd` formatting
-    line.push_str("  ");
+    dumper.dump(input, &mut output)?;
 
-    // Convert bytes to ASCII or placeholder characters
-    line.extend(buffer.iter().map(|&b| match b {
-        // Printable characters: SP (0x20) to ~ (0x7e)
-        0x20..=0x7e => b as char,
-        _ => '.',
-    }));
+    // Output files are only replaced once everything was written
+    output
+        .finish()
+        .map_err(Error::io("failed to write to output"))?;
 
     Ok(())
 }
@@ -176,12 +150,31 @@ fn format_hex_dump_line(
 /// Performs a reconstruction of binary data from a hex dump using the given `Config`.
 ///
 /// Each input line is expected to be formatted similarly to `xxd` output:
-/// - An 8-digit hex offset followed by a colon and a space,
+/// - A hex offset of any width (up to `u64::MAX`) followed by a colon and a space,
 /// - A hex byte section (grouping and column width do not affect parsing).
 /// - Two spaces separating hex bytes from ASCII representation (which is ignored).
 ///
-/// The function extracts only hex byte sections, converts them back to binary,
-/// and writes them sequentially to the specified output stream.
+/// Lines are parsed as raw bytes, so the ASCII representation may hold anything, including
+/// invalid UTF-8.
+///
+/// The function converts the hex byte sections back to binary and writes each one at
+/// the position given by its offset, matching `xxd` patch semantics. Gaps between offsets
+/// are skipped on seekable outputs (leaving existing data untouched) and filled with zeros
+/// on streams. If `seek` is set, it is added to every offset found in the hex dump.
+///
+/// Offsets are parsed in decimal if `decimal` is set, and `display_offset` is subtracted
+/// from them to undo the shift applied when dumping.
+///
+/// A line consisting of `*` (as produced by `autoskip` or `squeeze`) stands for lines
+/// identical to the previous one, which are repeated up to the offset of the next line.
+///
+/// If `format` is `Format::Plain`, the input is instead treated as a continuous stream of
+/// hex digits: whitespace and line structure are ignored, and bytes are written
+/// sequentially (starting at the `seek` displacement, if set).
+///
+/// If `format` is `Format::Include`, the comma-separated elements of a C array (e.g.,
+/// `0x4a`) are written sequentially in the same way. Text before the opening `{` and after
+/// the closing `}` is ignored, so the array declarations may be present or absent.
 ///
 /// # Example
 ///
@@ -190,8 +183,7 @@ fn format_hex_dump_line(
 ///     cols: 16,
 ///     byte_groups: 2,
 ///     reverse: true,
-///     input: Box::new(std::io::stdin()),
-///     output: Box::new(std::io::stdout()),
+///     ..Default::default()
 /// };
 ///
 /// if let Err(err) = hxx::reverse_hex_dump(config) {
@@ -205,76 +197,457 @@ fn format_hex_dump_line(
 /// This function returns an error if:
 /// - It fails to read from the input stream.
 /// - It fails to write to the output stream.
-/// - The input data is invalid or malformed in reverse mode.
-pub fn reverse_hex_dump(config: Config) -> Result<(), String> {
+/// - It fails to seek in the output stream (e.g., backwards on a stream).
+/// - The input data is invalid or malformed in reverse mode, in which case `Error::Parse`
+///   locates the problem in the input.
+pub fn reverse_hex_dump(config: Config) -> Result<(), Error> {
+    let displacement = match config.seek {
+        Some(SeekFrom::Start(n)) => {
+            i64::try_from(n).map_err(|_| Error::usage("invalid seek displacement"))?
+        }
+        Some(SeekFrom::Current(n) | SeekFrom::End(n)) => n,
+        None => 0,
+    };
+
+    let mut reverser = Reverser::new(&config.dumper(), displacement);
+
     // Buffer I/O to minimize syscall overhead
-    let mut reader = BufReader::new(config.input);
-    let mut writer = BufWriter::new(config.output);
+    let mut reader = BufReader::with_capacity(BLOCK_SIZE, config.input);
+    let mut writer = BufWriter::with_capacity(BLOCK_SIZE, config.output);
+
+    if config.jobs > 1 && reverser.has_offsets() {
+        parallel::reverse(&mut reverser, config.jobs, &mut reader, &mut writer)?;
+    }
 
-    let mut line = Vec::with_capacity(1024);
-    let mut buf = String::with_capacity(1024);
+    // Lines are kept as raw bytes, so the text column may hold anything
+    let mut buf = Vec::with_capacity(1024);
 
     loop {
+        // Reset buffer since `read_until()` preserves buffer contents
+        buf.clear();
+
         let bytes_read = reader
-            .read_line(&mut buf)
-            .map_err(|err| format!("failed to read from input: {err}"))?;
+            .read_until(b'\n', &mut buf)
+            .map_err(Error::io("failed to read from input"))?;
 
         // Check for EOF
         if bytes_read == 0 {
             break;
         }
 
-        format_reverse_hex_dump_line(&mut line, &buf[..bytes_read])?;
+        reverser.decode_line(&buf, &mut writer)?;
+    }
+
+    reverser.finish()?;
+
+    // Output files are only replaced once everything was written
+    writer
+        .into_inner()
+        .map_err(|err| Error::io("failed to write to output")(err.into_error()))?
+        .finish()
+        .map_err(Error::io("failed to write to output"))?;
+
+    Ok(())
+}
+
+// Destination of the bytes decoded from a hex dump, written at the offsets of the lines
+pub(crate) trait Patch {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    // Moves from the absolute position `current` to the absolute position `target`
+    fn seek(&mut self, current: u64, target: u64) -> io::Result<()>;
+
+    // Writes `len` bytes repeating `bytes`, filling the lines skipped by a `*` line
+    fn repeat(&mut self, bytes: &[u8], len: u64) -> io::Result<()> {
+        let mut left = len;
+
+        while left > 0 {
+            let chunk = &bytes[..bytes.len().min(usize::try_from(left).unwrap_or(usize::MAX))];
+            self.write_bytes(chunk)?;
+            left -= chunk.len() as u64;
+        }
+
+        Ok(())
+    }
+}
+
+impl Patch for BufWriter<Output> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_all(bytes)
+    }
+
+    fn seek(&mut self, current: u64, target: u64) -> io::Result<()> {
+        // Pending bytes must be written before repositioning
+        self.flush()?;
+        self.get_mut().seek(current, target)
+    }
+}
+
+// Decoded bytes are buffered in memory, so gaps are filled with zeros like a stream
+#[cfg(test)]
+impl Patch for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn seek(&mut self, current: u64, target: u64) -> io::Result<()> {
+        let gap = target.checked_sub(current).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek backwards on non-seekable output",
+            )
+        })?;
+
+        self.resize(self.len() + gap as usize, 0);
+        Ok(())
+    }
+}
+
+// Reverse hex dump in progress, decoding one line of the hex dump at a time
+pub(crate) struct Reverser {
+    line_format: LineFormat,
+    // Added to every offset found in the hex dump
+    displacement: i64,
+
+    // Current write position in the output
+    position: u64,
+
+    // Plain and C include dumps carry no offsets, so they are tracked from the bytes
+    // decoded so far
+    sequential_offset: u64,
+    pending_nibble: Option<u8>,
+    array_state: ArrayState,
+
+    line: Vec<u8>,
+
+    // Previous line of input, kept to locate problems only detected at the end of input
+    prev_buf: Vec<u8>,
+    line_number: usize,
+
+    // Bytes of the previous line, repeated to fill the lines skipped by a `*` line
+    prev_line: Vec<u8>,
+    repeat_pending: bool,
+}
+
+impl Reverser {
+    pub(crate) fn new(dumper: &Dumper, displacement: i64) -> Self {
+        Self {
+            line_format: LineFormat::new(dumper),
+            displacement,
+            position: 0,
+            sequential_offset: 0,
+            pending_nibble: None,
+            array_state: ArrayState::Unknown,
+            line: Vec::with_capacity(1024),
+            prev_buf: Vec::with_capacity(1024),
+            line_number: 0,
+            prev_line: Vec::with_capacity(1024),
+            repeat_pending: false,
+        }
+    }
+
+    // Counts lines from `line_number`, for hex dumps decoded from the middle of the input
+    pub(crate) fn start_at_line(mut self, line_number: usize) -> Self {
+        self.line_number = line_number;
+        self
+    }
+
+    // Position in the output after the bytes of the last line written
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    // Whether lines carry their own offsets, so they can be parsed independently of each other
+    pub(crate) fn has_offsets(&self) -> bool {
+        matches!(
+            self.line_format.format,
+            Format::Normal | Format::Bits | Format::LittleEndian
+        )
+    }
 
-        writer
-            .write_all(&line)
-            .map_err(|err| format!("failed to write to output: {err}"))?;
+    // Decodes a line of the hex dump (with or without its line terminator) into `output`
+    pub(crate) fn decode_line(&mut self, buf: &[u8], output: &mut impl Patch) -> Result<(), Error> {
+        let line_number = self.line_number + 1;
+        let malformed = |err: Malformed| Error::parse(line_number, buf, err.index, err.message);
 
-        // Reset buffer before reading again to avoid extra allocations
+        // Reuse the line buffer to avoid extra allocations
+        let mut line = std::mem::take(&mut self.line);
         line.clear();
 
-        // Reset buffer since `read_line()` preserves buffer contents
-        buf.clear();
+        let target = match self.line_format.format {
+            Format::Normal | Format::Bits | Format::LittleEndian => {
+                self.parse_line(buf, line_number, &mut line)
+            }
+            Format::Plain => {
+                format_reverse_plain_hex_dump_line(&mut line, buf, &mut self.pending_nibble)
+                    .map_err(malformed)
+                    .and_then(|()| self.target(self.sequential_offset).map_err(malformed))
+                    .map(Some)
+            }
+            Format::Include => {
+                format_reverse_include_hex_dump_line(&mut line, buf, &mut self.array_state)
+                    .map_err(malformed)
+                    .and_then(|()| self.target(self.sequential_offset).map_err(malformed))
+                    .map(Some)
+            }
+        };
+
+        let result = target.and_then(|target| self.write_line(buf, target, &line, output));
+
+        self.line = line;
+        result
     }
 
-    Ok(())
+    // Decodes a line of a hex dump with offsets into `line`, returning the position of its
+    // bytes in the output, or `None` for a `*` line. Lines with offsets do not depend on the
+    // lines before them, so they may be parsed in any order
+    pub(crate) fn parse_line(
+        &self,
+        buf: &[u8],
+        line_number: usize,
+        line: &mut Vec<u8>,
+    ) -> Result<Option<u64>, Error> {
+        let malformed = |err: Malformed| Error::parse(line_number, buf, err.index, err.message);
+
+        if buf.trim_ascii() == b"*" {
+            return Ok(None);
+        }
+
+        let offset =
+            format_reverse_hex_dump_line(line, buf, &self.line_format).map_err(malformed)?;
+
+        // Undo the display offset applied when dumping
+        let offset = offset
+            .checked_sub(self.line_format.display_offset)
+            .ok_or_else(|| {
+                malformed(Malformed::new(
+                    0,
+                    "malformed line: offset below display offset",
+                ))
+            })?;
+
+        self.target(offset).map(Some).map_err(malformed)
+    }
+
+    // Position in the output of the bytes found at `offset` in the hex dump
+    fn target(&self, offset: u64) -> Result<u64, Malformed> {
+        offset
+            .checked_add_signed(self.displacement)
+            .ok_or(Malformed::new(0, "malformed line: offset out of range"))
+    }
+
+    // Writes the bytes decoded from the line `buf` at `target` in `output`, or repeats the
+    // previous line if `target` is `None`. Lines must be written in order
+    pub(crate) fn write_line(
+        &mut self,
+        buf: &[u8],
+        target: Option<u64>,
+        bytes: &[u8],
+        output: &mut impl Patch,
+    ) -> Result<(), Error> {
+        self.line_number += 1;
+
+        // Keep the line, to locate problems only detected at the end of input
+        self.prev_buf.clear();
+        self.prev_buf.extend_from_slice(buf);
+
+        let Some(target) = target else {
+            if self.prev_line.is_empty() {
+                let index = find_byte(buf, b'*').unwrap_or(0);
+                return Err(Error::parse(
+                    self.line_number,
+                    buf,
+                    index,
+                    "malformed line: '*' without a preceding line",
+                ));
+            }
+
+            self.repeat_pending = true;
+            return Ok(());
+        };
+
+        // Bytes past the end of the `u64` range could never be written
+        let end = target.checked_add(bytes.len() as u64).ok_or_else(|| {
+            Error::parse(
+                self.line_number,
+                buf,
+                0,
+                "malformed line: offset out of range",
+            )
+        })?;
+
+        // Skipped lines span from the end of the previous line up to this line
+        if self.repeat_pending {
+            if self.position < target {
+                output
+                    .repeat(&self.prev_line, target - self.position)
+                    .map_err(Error::io("failed to write to output"))?;
+
+                self.position = target;
+            }
+
+            self.repeat_pending = false;
+        }
+
+        if target != self.position {
+            output
+                .seek(self.position, target)
+                .map_err(Error::io("failed to seek output"))?;
+        }
+
+        output
+            .write_bytes(bytes)
+            .map_err(Error::io("failed to write to output"))?;
+
+        self.position = end;
+        self.sequential_offset += bytes.len() as u64;
+
+        // Keep the decoded line, repeated by a following `*` line
+        self.prev_line.clear();
+        self.prev_line.extend_from_slice(bytes);
+
+        Ok(())
+    }
+
+    // Checks that the hex dump did not end in the middle of something
+    pub(crate) fn finish(&self) -> Result<(), Error> {
+        // Problems only detected at the end of input are located at the end of the last line
+        let at_last_line = |message| {
+            let index = self.prev_buf.trim_ascii_end().len();
+            Error::parse(self.line_number, &self.prev_buf, index, message)
+        };
+
+        if self.pending_nibble.is_some() {
+            return Err(at_last_line("malformed hex: odd number of hex digits"));
+        }
+
+        if self.repeat_pending {
+            return Err(at_last_line(
+                "malformed line: '*' must be followed by a line",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// Problem found while decoding a line of a hex dump, at the byte `index` of the line
+struct Malformed {
+    index: usize,
+    message: &'static str,
+}
+
+impl Malformed {
+    fn new(index: usize, message: &'static str) -> Self {
+        Self { index, message }
+    }
+
+    // Relocates a problem found in a section of the line starting at the byte `start`
+    fn shift(self, start: usize) -> Self {
+        Self {
+            index: self.index + start,
+            ..self
+        }
+    }
 }
 
-fn format_reverse_hex_dump_line(line: &mut Vec<u8>, buffer: &str) -> Result<(), String> {
-    let colon_idx = buffer.find(':').ok_or("malformed line: missing ':'")?;
+// Decodes the hex byte section of `buffer` into `line`, returning the offset of the line
+fn format_reverse_hex_dump_line(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    line_format: &LineFormat,
+) -> Result<u64, Malformed> {
+    let LineFormat {
+        cols,
+        byte_groups,
+        format,
+        decimal,
+        ..
+    } = *line_format;
+
+    let offset_start = buffer.len() - buffer.trim_ascii_start().len();
+
+    let colon_idx = find_byte(buffer, b':').ok_or_else(|| {
+        // The colon is expected right after the offset digits
+        let index = buffer[offset_start..]
+            .iter()
+            .position(|&b| HEX_VALUES[b as usize] >= 16)
+            .map_or(buffer.len(), |index| index + offset_start);
+
+        Malformed::new(index, "malformed line: missing ':'")
+    })?;
+
+    // Offsets may have any width, as long as their value fits in a `u64`
+    let radix = if decimal { 10 } else { 16 };
+    let digits = buffer[..colon_idx].trim_ascii();
+    let offset = parse_number(digits, radix).ok_or_else(|| {
+        let valid = !digits.is_empty()
+            && digits
+                .iter()
+                .all(|&b| u64::from(HEX_VALUES[b as usize]) < radix);
+
+        match valid {
+            true => Malformed::new(offset_start, "malformed line: offset out of range"),
+            false => Malformed::new(offset_start, "malformed line: invalid offset"),
+        }
+    })?;
 
     // Skip colon and additional space
-    let start = colon_idx + 2;
+    let start = (colon_idx + 2).min(buffer.len());
+
+    if format == Format::LittleEndian {
+        format_reverse_little_endian_section(line, &buffer[start..], cols, byte_groups)
+            .map_err(|err| err.shift(start))?;
+        return Ok(offset);
+    }
 
     let end = buffer[start..]
-        .find("  ")
-        .ok_or("malformed line: missing double space separator")?
+        .windows(2)
+        .position(|pair| pair == b"  ")
+        .ok_or_else(|| {
+            Malformed::new(
+                buffer.trim_ascii_end().len(),
+                "malformed line: missing double space separator",
+            )
+        })?
         + start;
 
-    if end > buffer.len() {
-        return Err("malformed line: line too short".into());
+    let hex = &buffer[start..end];
+
+    if format == Format::Bits {
+        format_reverse_bits_section(line, hex).map_err(|err| err.shift(start))?;
+        return Ok(offset);
     }
 
-    let hex = &buffer[start..end];
+    format_reverse_hex_digits(line, hex).map_err(|err| err.shift(start))?;
+
+    Ok(offset)
+}
 
-    let mut chars = hex.chars().filter(|c| !c.is_whitespace());
+// Decodes pairs of hex digits of `hex` into `line`, ignoring whitespace
+fn format_reverse_hex_digits(line: &mut Vec<u8>, hex: &[u8]) -> Result<(), Malformed> {
+    let mut digits = hex
+        .iter()
+        .enumerate()
+        .map(|(idx, &b)| (idx, HEX_VALUES[b as usize]))
+        .filter(|&(_, value)| value != SPACE);
 
     // Process one octet at a time
-    loop {
-        let high = match chars.next() {
-            Some(c) => c,
-            None => break,
-        };
+    while let Some((high_idx, high_nibble)) = digits.next() {
+        let (low_idx, low_nibble) = digits.next().ok_or(Malformed::new(
+            high_idx,
+            "malformed hex: odd number of hex digits",
+        ))?;
 
-        let low = chars
-            .next()
-            .ok_or("malformed hex: odd number of hex digits")?;
+        // Both values must be 4-bit numbers, rather than one of the markers
+        if high_nibble >= 16 {
+            return Err(Malformed::new(high_idx, INVALID_HEX));
+        }
 
-        // Convert both hex characters to 4-bit numeric values
-        let high_nibble = high
-            .to_digit(16)
-            .ok_or("malformed line: invalid hex char")? as u8;
-        let low_nibble = low.to_digit(16).ok_or("malformed line: invalid hex char")? as u8;
+        if low_nibble >= 16 {
+            return Err(Malformed::new(low_idx, INVALID_HEX));
+        }
 
         // Combine the two 4-bit nibbles into a full 8-bit byte
         // Shifts `high_nibble` into the upper 4 bits and merges it with `low_nibble`
@@ -297,105 +670,1288 @@ fn format_reverse_hex_dump_line(line: &mut Vec<u8>, buffer: &str) -> Result<(),
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Decodes the little-endian words of a hex section back into memory order. Partial groups are
+// right-aligned, so the section may contain runs of spaces and is instead delimited by the
+// width of a full line
+fn format_reverse_little_endian_section(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    cols: usize,
+    byte_groups: usize,
+) -> Result<(), Malformed> {
+    let buffer = trim_line_terminator(buffer);
 
-    #[test]
-    fn test_missing_colon() {
-        let input = Cursor::new("00000000  48 65 6c 6c 6f 20 77 6f  72 6c 64\n");
-        let output = Cursor::new(Vec::new());
+    let width = cols.div_ceil(byte_groups) * (byte_groups * 2 + 1) - 1;
+    let hex = &buffer[..width.min(buffer.len())];
 
-        let config = Config {
-            cols: 16,
-            byte_groups: 2,
-            reverse: true,
-            input: Box::new(input),
-            output: Box::new(output),
+    // Each piece is a group followed by a single whitespace character, if any
+    let mut group_start = 0;
+
+    for piece in hex.split_inclusive(|&b| HEX_VALUES[b as usize] == SPACE) {
+        let group = piece.trim_ascii_end();
+        let line_start = line.len();
+
+        format_reverse_hex_digits(line, group).map_err(|err| err.shift(group_start))?;
+        line[line_start..].reverse();
+
+        group_start += piece.len();
+    }
+
+    Ok(())
+}
+
+// Decodes groups of 8 binary digits (most significant bit first) into `line`, ignoring
+// whitespace
+fn format_reverse_bits_section(line: &mut Vec<u8>, bits: &[u8]) -> Result<(), Malformed> {
+    let mut byte: u8 = 0;
+    let mut count = 0;
+    let mut last_idx = 0;
+
+    for (idx, &b) in bits.iter().enumerate() {
+        let bit = match b {
+            b'0' => 0,
+            b'1' => 1,
+            _ if HEX_VALUES[b as usize] == SPACE => continue,
+            _ => {
+                return Err(Malformed::new(idx, "malformed line: invalid binary digit"));
+            }
         };
 
-        let result = reverse_hex_dump(config);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("missing ':'"));
+        byte = (byte << 1) | bit;
+        count += 1;
+        last_idx = idx;
+
+        if count == 8 {
+            line.push(byte);
+            byte = 0;
+            count = 0;
+        }
     }
 
-    #[test]
-    fn test_missing_double_space_separator() {
-        let input = Cursor::new("00000000: 48 65 6c 6c 6f 20 776f726c64\n");
-        let output = Cursor::new(Vec::new());
+    if count != 0 {
+        return Err(Malformed::new(
+            last_idx,
+            "malformed line: incomplete octet of binary digits",
+        ));
+    }
 
-        let config = Config {
-            cols: 16,
-            byte_groups: 2,
-            reverse: true,
-            input: Box::new(input),
-            output: Box::new(output),
+    Ok(())
+}
+
+// Decodes every hex digit of `buffer` into `line`, ignoring whitespace. A trailing unpaired
+// digit is kept in `pending_nibble`, since octets may be split across lines
+fn format_reverse_plain_hex_dump_line(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    pending_nibble: &mut Option<u8>,
+) -> Result<(), Malformed> {
+    for (idx, &b) in buffer.iter().enumerate() {
+        let nibble = match HEX_VALUES[b as usize] {
+            SPACE => continue,
+            NOT_HEX => return Err(Malformed::new(idx, INVALID_HEX)),
+            nibble => nibble,
         };
 
-        let result = reverse_hex_dump(config);
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .contains("missing double space separator")
-        );
+        match pending_nibble.take() {
+            Some(high_nibble) => line.push((high_nibble << 4) | nibble),
+            None => *pending_nibble = Some(nibble),
+        }
     }
 
-    #[test]
-    fn test_line_too_short() {
-        let input = Cursor::new("00000000: 48\n");
-        let output = Cursor::new(Vec::new());
+    Ok(())
+}
 
-        let config = Config {
-            cols: 16,
-            byte_groups: 2,
-            reverse: true,
-            input: Box::new(input),
-            output: Box::new(output),
+// Progress through a C array when reversing a C include dump
+enum ArrayState {
+    // No `{` seen yet; lines may be declarations or bare elements
+    Unknown,
+    Elements,
+    // Closing `}` seen; everything that follows is ignored
+    Done,
+}
+
+// Decodes the C array elements of `buffer` into `line`, skipping any surrounding declarations
+fn format_reverse_include_hex_dump_line(
+    line: &mut Vec<u8>,
+    buffer: &[u8],
+    state: &mut ArrayState,
+) -> Result<(), Malformed> {
+    let (start, mut elements) = match state {
+        ArrayState::Unknown => match find_byte(buffer, b'{') {
+            Some(idx) => (idx + 1, &buffer[idx + 1..]),
+            // Without a declaration, the input consists of bare elements
+            None => (0, buffer),
+        },
+        ArrayState::Elements => (0, buffer),
+        ArrayState::Done => return Ok(()),
+    };
+
+    *state = ArrayState::Elements;
+
+    if let Some(idx) = find_byte(elements, b'}') {
+        elements = &elements[..idx];
+        *state = ArrayState::Done;
+    }
+
+    let mut element_start = start;
+
+    for piece in elements.split_inclusive(|&b| b == b',') {
+        let element = piece.strip_suffix(b",").unwrap_or(piece).trim_ascii();
+        let element_idx = element_start + piece.len() - piece.trim_ascii_start().len();
+
+        element_start += piece.len();
+
+        // Trailing commas and blank lines leave empty elements
+        if element.is_empty() {
+            continue;
+        }
+
+        let value = match element
+            .strip_prefix(b"0x")
+            .or_else(|| element.strip_prefix(b"0X"))
+        {
+            Some(hex) => parse_number(hex, 16),
+            None => parse_number(element, 10),
         };
 
-        let result = reverse_hex_dump(config);
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .contains("missing double space separator")
+        line.push(
+            value
+                .and_then(|value| u8::try_from(value).ok())
+                .ok_or(Malformed::new(
+                    element_idx,
+                    "malformed line: invalid C array element",
+                ))?,
         );
     }
 
-    #[test]
-    fn test_odd_number_of_hex_digits() {
-        let input = Cursor::new("00000000: 4 8 6 5 6 c 6 c 6 f 2 0 7 7 6 f 7 2 6 c 6  \n");
-        let output = Cursor::new(Vec::new());
+    Ok(())
+}
 
-        let config = Config {
-            cols: 16,
-            byte_groups: 2,
-            reverse: true,
-            input: Box::new(input),
-            output: Box::new(output),
-        };
+const INVALID_HEX: &str = "malformed line: invalid hex char";
 
-        let result = reverse_hex_dump(config);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("odd number of hex digits"));
+// Value of every byte as a hex digit, or one of the markers below, so that lines are decoded
+// byte by byte with a single lookup, without validating or decoding UTF-8
+static HEX_VALUES: [u8; 256] = hex_values();
+
+// Bytes that are not hex digits
+const NOT_HEX: u8 = 0xff;
+
+// ASCII whitespace (including vertical tab), which separates hex digits
+const SPACE: u8 = 0xfe;
+
+const fn hex_values() -> [u8; 256] {
+    let mut table = [NOT_HEX; 256];
+    let mut digit = 0;
+
+    while digit < 10 {
+        table[b'0' as usize + digit] = digit as u8;
+        digit += 1;
     }
 
-    #[test]
-    fn test_invalid_hex_character() {
-        let input = Cursor::new("00000000: 48 65 6c 6c 6f 2G 77 6f 72 6c 64  \n");
-        let output = Cursor::new(Vec::new());
+    let mut digit = 0;
 
-        let config = Config {
-            cols: 16,
-            byte_groups: 2,
-            reverse: true,
-            input: Box::new(input),
-            output: Box::new(output),
-        };
+    while digit < 6 {
+        table[b'a' as usize + digit] = 10 + digit as u8;
+        table[b'A' as usize + digit] = 10 + digit as u8;
+        digit += 1;
+    }
 
-        let result = reverse_hex_dump(config);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("invalid hex char"));
+    let mut i = 0;
+    let spaces = b" \t\n\x0b\x0c\r";
+
+    while i < spaces.len() {
+        table[spaces[i] as usize] = SPACE;
+        i += 1;
+    }
+
+    table
+}
+
+// Parses the digits of `digits` in `radix` (10 or 16), without sign or whitespace
+fn parse_number(digits: &[u8], radix: u64) -> Option<u64> {
+    if digits.is_empty() {
+        return None;
+    }
+
+    digits.iter().try_fold(0u64, |value, &b| {
+        let digit = HEX_VALUES[b as usize] as u64;
+
+        if digit >= radix {
+            return None;
+        }
+
+        value.checked_mul(radix)?.checked_add(digit)
+    })
+}
+
+fn find_byte(buffer: &[u8], byte: u8) -> Option<usize> {
+    buffer.iter().position(|&b| b == byte)
+}
+
+fn trim_line_terminator(buffer: &[u8]) -> &[u8] {
+    let end = buffer
+        .iter()
+        .rposition(|&b| b != b'\r' && b != b'\n')
+        .map_or(0, |idx| idx + 1);
+
+    &buffer[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::SharedBuf;
+    use crate::{Charset, Input};
+
+    // Input returning at most `chunk` bytes per read, like a pipe or terminal, and failing
+    // every other read with `Interrupted` if `interrupt` is set
+    struct ChunkedReader {
+        data: &'static [u8],
+        chunk: usize,
+        interrupt: bool,
+        interrupted: bool,
+    }
+
+    impl ChunkedReader {
+        fn new(data: &'static [u8], chunk: usize, interrupt: bool) -> Self {
+            Self {
+                data,
+                chunk,
+                interrupt,
+                interrupted: false,
+            }
+        }
+    }
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.interrupt && !self.interrupted {
+                self.interrupted = true;
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+
+            self.interrupted = false;
+
+            let n = buf.len().min(self.chunk).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+
+            Ok(n)
+        }
+    }
+
+    fn dump(input: Input, seek: Option<SeekFrom>, len: Option<u64>) -> Result<String, Error> {
+        dump_with(Config {
+            seek,
+            len,
+            input,
+            ..Default::default()
+        })
+    }
+
+    fn dump_with(config: Config) -> Result<String, Error> {
+        let output = SharedBuf::default();
+
+        let config = Config {
+            output: Output::Stream(Box::new(output.clone())),
+            ..config
+        };
+
+        hex_dump(config)?;
+        Ok(String::from_utf8(output.contents()).unwrap())
+    }
+
+    fn reverse(input: &str, output: Output, seek: Option<SeekFrom>) -> Result<(), Error> {
+        reverse_with(
+            input,
+            output,
+            Config {
+                seek,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn reverse_with(input: &str, output: Output, config: Config) -> Result<(), Error> {
+        let config = Config {
+            reverse: true,
+            input: Input::Stream(Box::new(Cursor::new(input.to_string()))),
+            output,
+            ..config
+        };
+
+        reverse_hex_dump(config)
+    }
+
+    const HELLO: &[u8] = b"Hello, world! This is hxx.";
+
+    #[test]
+    fn test_seek_absolute() {
+        let input = Input::Seekable(Box::new(Cursor::new(HELLO)));
+        let output = dump(input, Some(SeekFrom::Start(7)), None).unwrap();
+
+        assert_eq!(
+            output,
+            "00000007: 776f 726c 6421 2054 6869 7320 6973 2068  world! This is h\n\
+             00000017: 7878 2e                                  xx.\n"
+        );
+    }
+
+    #[test]
+    fn test_seek_from_end_with_len() {
+        let input = Input::Seekable(Box::new(Cursor::new(HELLO)));
+        let output = dump(input, Some(SeekFrom::End(-5)), Some(3)).unwrap();
+
+        assert_eq!(
+            output,
+            "00000015: 2068 78                                   hx\n"
+        );
+    }
+
+    #[test]
+    fn test_seek_stream_discards() {
+        let input = Input::Stream(Box::new(Cursor::new(HELLO)));
+        let output = dump(input, Some(SeekFrom::Current(21)), Some(2)).unwrap();
+
+        assert_eq!(
+            output,
+            "00000015: 2068                                      h\n"
+        );
+    }
+
+    #[test]
+    fn test_seek_stream_from_end() {
+        let input = Input::Stream(Box::new(Cursor::new(HELLO)));
+        let result = dump(input, Some(SeekFrom::End(-5)), None);
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("failed to seek input")
+        );
+    }
+
+    #[test]
+    fn test_short_reads_fill_lines() {
+        let expected = dump(Input::Stream(Box::new(HELLO)), None, None).unwrap();
+
+        for chunk in [1, 3, 7, 16] {
+            let input = Input::Stream(Box::new(ChunkedReader::new(HELLO, chunk, false)));
+            assert_eq!(dump(input, None, None).unwrap(), expected, "chunk {chunk}");
+        }
+    }
+
+    #[test]
+    fn test_short_reads_with_seek_and_len() {
+        let expected = dump(
+            Input::Stream(Box::new(HELLO)),
+            Some(SeekFrom::Start(3)),
+            Some(20),
+        )
+        .unwrap();
+
+        let input = Input::Stream(Box::new(ChunkedReader::new(HELLO, 5, false)));
+        assert_eq!(
+            dump(input, Some(SeekFrom::Start(3)), Some(20)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_interrupted_reads_retried() {
+        let expected = dump(Input::Stream(Box::new(HELLO)), None, None).unwrap();
+
+        let input = Input::Stream(Box::new(ChunkedReader::new(HELLO, 4, true)));
+        assert_eq!(dump(input, None, None).unwrap(), expected);
+
+        let config = Config {
+            format: Format::Plain,
+            input: Input::Stream(Box::new(ChunkedReader::new(HELLO, 2, true))),
+            ..Default::default()
+        };
+        assert_eq!(
+            dump_with(config).unwrap(),
+            "48656c6c6f2c20776f726c6421205468\n6973206973206878782e\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_dump() {
+        let output = dump_with(Config {
+            cols: 10,
+            format: Format::Plain,
+            input: Input::Stream(Box::new(Cursor::new(HELLO))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "48656c6c6f2c20776f72\n\
+             6c642120546869732069\n\
+             73206878782e\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_reverse_ignores_line_structure() {
+        let output = SharedBuf::default();
+        let input = "48 65\n6c\t6c 6\nf\n";
+
+        let config = Config {
+            format: Format::Plain,
+            ..Default::default()
+        };
+        reverse_with(input, Output::Stream(Box::new(output.clone())), config).unwrap();
+
+        assert_eq!(output.contents(), b"Hello");
+    }
+
+    #[test]
+    fn test_plain_reverse_odd_number_of_hex_digits() {
+        let config = Config {
+            format: Format::Plain,
+            ..Default::default()
+        };
+        let result = reverse_with("486", Output::Stream(Box::new(io::sink())), config);
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("odd number of hex digits")
+        );
+    }
+
+    #[test]
+    fn test_uneven_byte_groups_padding() {
+        let output = dump_with(Config {
+            cols: 10,
+            byte_groups: 4,
+            input: Input::Stream(Box::new(Cursor::new(&HELLO[..17]))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: 48656c6c 6f2c2077 6f72  Hello, wor\n\
+             0000000a: 6c642120 546869         ld! Thi\n"
+        );
+    }
+
+    #[test]
+    fn test_bits_dump() {
+        let output = dump_with(Config {
+            cols: 6,
+            byte_groups: 1,
+            format: Format::Bits,
+            input: Input::Stream(Box::new(Cursor::new(&HELLO[..13]))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: 01001000 01100101 01101100 01101100 01101111 00101100  Hello,\n\
+             00000006: 00100000 01110111 01101111 01110010 01101100 01100100   world\n\
+             0000000c: 00100001                                               !\n"
+        );
+    }
+
+    #[test]
+    fn test_little_endian_dump() {
+        let output = dump_with(Config {
+            cols: 16,
+            byte_groups: 4,
+            format: Format::LittleEndian,
+            input: Input::Stream(Box::new(Cursor::new(HELLO))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: 6c6c6548 77202c6f 646c726f 68542021  Hello, world! Th\n\
+             00000010: 69207369 78682073     2e78           is is hxx.\n"
+        );
+    }
+
+    #[test]
+    fn test_little_endian_round_trip() {
+        for (cols, byte_groups) in [(16, 4), (6, 4), (10, 8), (16, 2)] {
+            let output = dump_with(Config {
+                cols,
+                byte_groups,
+                format: Format::LittleEndian,
+                input: Input::Stream(Box::new(Cursor::new(HELLO))),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let reversed = SharedBuf::default();
+            let config = Config {
+                cols,
+                byte_groups,
+                format: Format::LittleEndian,
+                ..Default::default()
+            };
+            reverse_with(&output, Output::Stream(Box::new(reversed.clone())), config).unwrap();
+
+            assert_eq!(reversed.contents(), HELLO);
+        }
+    }
+
+    #[test]
+    fn test_bits_reverse() {
+        let output = SharedBuf::default();
+        let input = "00000000: 01001000 0110010101101100  Hel\n\
+                     00000003: 01101100 01101111                    lo\n";
+
+        let config = Config {
+            format: Format::Bits,
+            ..Default::default()
+        };
+        reverse_with(input, Output::Stream(Box::new(output.clone())), config).unwrap();
+
+        assert_eq!(output.contents(), b"Hello");
+    }
+
+    #[test]
+    fn test_bits_reverse_incomplete_octet() {
+        let config = Config {
+            format: Format::Bits,
+            ..Default::default()
+        };
+        let input = "00000000: 01001000 0110010  He\n";
+        let result = reverse_with(input, Output::Stream(Box::new(io::sink())), config);
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("incomplete octet"));
+    }
+
+    #[test]
+    fn test_uppercase_dump() {
+        let dump = |format| {
+            dump_with(Config {
+                cols: 4,
+                format,
+                uppercase: true,
+                name: Some(String::from("hi")),
+                seek: Some(SeekFrom::Start(10)),
+                len: Some(4),
+                input: Input::Seekable(Box::new(Cursor::new(HELLO))),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        assert_eq!(dump(Format::Normal), "0000000A: 6C64 2120  ld! \n");
+        assert_eq!(dump(Format::Plain), "6C642120\n");
+        assert_eq!(
+            dump(Format::Include),
+            "unsigned char hi[] = {\n  0X6C, 0X64, 0X21, 0X20\n};\nunsigned int hi_len = 4;\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_mixed_case() {
+        let output = SharedBuf::default();
+        let input = "00000000: 4865 6C6c  HelL\n0000000A: 6f2C  o,\n0000000c: 2a2A  **\n";
+
+        reverse(input, Output::Stream(Box::new(output.clone())), None).unwrap();
+
+        assert_eq!(output.contents(), b"Hell\0\0\0\0\0\0o,**");
+
+        let config = Config {
+            format: Format::Plain,
+            ..Default::default()
+        };
+        let output = SharedBuf::default();
+        reverse_with(
+            "4865\n6C6c\n6F",
+            Output::Stream(Box::new(output.clone())),
+            config,
+        )
+        .unwrap();
+
+        assert_eq!(output.contents(), b"Hello");
+    }
+
+    #[test]
+    fn test_display_offset_dump() {
+        let output = dump_with(Config {
+            cols: 8,
+            display_offset: 0x1000,
+            seek: Some(SeekFrom::Start(4)),
+            len: Some(12),
+            input: Input::Seekable(Box::new(Cursor::new(HELLO))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00001004: 6f2c 2077 6f72 6c64  o, world\n\
+             0000100c: 2120 5468            ! Th\n"
+        );
+    }
+
+    #[test]
+    fn test_decimal_offset_dump() {
+        let output = dump_with(Config {
+            cols: 8,
+            decimal: true,
+            display_offset: 100,
+            input: Input::Stream(Box::new(Cursor::new(&HELLO[..20]))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000100: 4865 6c6c 6f2c 2077  Hello, w\n\
+             00000108: 6f72 6c64 2120 5468  orld! Th\n\
+             00000116: 6973 2069            is i\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_decimal_display_offset() {
+        let output = SharedBuf::default();
+        let input = "00000100: 4865 6c6c 6f2c 2077  Hello, w\n\
+                     00000116: 6973 2069            is i\n";
+
+        let config = Config {
+            decimal: true,
+            display_offset: 100,
+            ..Default::default()
+        };
+        reverse_with(input, Output::Stream(Box::new(output.clone())), config).unwrap();
+
+        assert_eq!(output.contents(), b"Hello, w\0\0\0\0\0\0\0\0is i");
+    }
+
+    #[test]
+    fn test_auto_offset_width_dump() {
+        // Offsets cross 4 GiB in the middle of the hex dump
+        let config = || Config {
+            cols: 8,
+            display_offset: 0xffff_fff0,
+            offset_width: None,
+            seek: Some(SeekFrom::Start(4)),
+            len: Some(16),
+            input: Input::Seekable(Box::new(Cursor::new(HELLO))),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_with(config()).unwrap(),
+            "0fffffff4: 6f2c 2077 6f72 6c64  o, world\n\
+             0fffffffc: 2120 5468 6973 2069  ! This i\n"
+        );
+
+        // Streams have an unknown size, which keeps the default width
+        let output = dump_with(Config {
+            display_offset: 0xffff_fff8,
+            seek: None,
+            input: Input::Stream(Box::new(Cursor::new(&HELLO[4..20]))),
+            ..config()
+        })
+        .unwrap();
+
+        assert!(output.starts_with("fffffff8: "), "{output}");
+        assert!(output.contains("\n100000000: "), "{output}");
+
+        let output = dump_with(Config {
+            offset_width: Some(12),
+            ..config()
+        })
+        .unwrap();
+
+        assert!(output.starts_with("0000fffffff4: "), "{output}");
+    }
+
+    #[test]
+    fn test_reverse_offsets_of_any_width() {
+        let output = SharedBuf::default();
+        let input = "0004: 6869  hi\n\
+                     000000000000000000000006: 2121  !!\n";
+
+        reverse(input, Output::Stream(Box::new(output.clone())), None).unwrap();
+        assert_eq!(output.contents(), b"\0\0\0\0hi!!");
+
+        // Offsets are only limited by the range of `u64`
+        let output = SharedBuf::default();
+        let input = "ffffffffffffff00: 6869  hi\n";
+
+        let config = Config {
+            display_offset: 0xffff_ffff_ffff_ff00,
+            ..Default::default()
+        };
+        reverse_with(input, Output::Stream(Box::new(output.clone())), config).unwrap();
+        assert_eq!(output.contents(), b"hi");
+
+        for input in [
+            "10000000000000000: 6869  hi\n",
+            "ffffffffffffffff: 6869  hi\n",
+        ] {
+            let Err(err) = reverse(input, Output::Stream(Box::new(io::sink())), None) else {
+                panic!("expected an error for {input:?}");
+            };
+
+            assert!(
+                err.to_string()
+                    .contains("offset out of range (line 1, column 1)"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_dump_keeps_alignment() {
+        let output = dump_with(Config {
+            cols: 8,
+            color: true,
+            input: Input::Stream(Box::new(Cursor::new(b"Hi\t\0\x01\xff"))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: \x1b[1;32m4869 \x1b[1;33m09\x1b[1;37m00 \x1b[1;31m01\x1b[1;34mff\x1b[0m\
+             \x20      \
+             \x1b[1;32mHi\x1b[1;33m.\x1b[1;37m.\x1b[1;31m.\x1b[1;34m.\x1b[0m\n"
+        );
+
+        // Without escapes, the line is identical to an uncolored one
+        let plain = dump_with(Config {
+            cols: 8,
+            input: Input::Stream(Box::new(Cursor::new(b"Hi\t\0\x01\xff"))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut stripped = output.clone();
+        for escape in [
+            "\x1b[1;32m",
+            "\x1b[1;33m",
+            "\x1b[1;37m",
+            "\x1b[1;31m",
+            "\x1b[1;34m",
+        ] {
+            stripped = stripped.replace(escape, "");
+        }
+
+        assert_eq!(stripped.replace("\x1b[0m", ""), plain);
+    }
+
+    #[test]
+    fn test_color_dump_by_charset() {
+        let dump = |charset, input: &'static [u8]| {
+            dump_with(Config {
+                cols: 4,
+                charset,
+                color: true,
+                input: Input::Stream(Box::new(input)),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        // Only 0xff is told apart from the other non-printable bytes
+        assert_eq!(
+            dump(Charset::Ascii, b"\xfe\xff"),
+            "00000000: \x1b[1;31mfe\x1b[1;34mff\x1b[0m       \
+             \x1b[1;31m.\x1b[1;34m.\x1b[0m\n"
+        );
+        assert_eq!(
+            dump(Charset::Ebcdic, b"\xc1\x41\x25\xff"),
+            "00000000: \x1b[1;32mc1\x1b[1;31m41 \x1b[1;33m25\x1b[1;34mff\x1b[0m  \
+             \x1b[1;32mA\x1b[1;31m.\x1b[1;33m.\x1b[1;34m.\x1b[0m\n"
+        );
+        assert_eq!(
+            dump(Charset::Latin1, b"\xe9\xff"),
+            "00000000: \x1b[1;32me9ff\x1b[0m       \x1b[1;32méÿ\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_charset_dump() {
+        let dump = |charset, input: &'static [u8]| {
+            dump_with(Config {
+                cols: 4,
+                charset,
+                placeholder: '_',
+                input: Input::Stream(Box::new(input)),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            dump(Charset::Ebcdic, b"\xc8\x85\x93\x00"),
+            "00000000: c885 9300  Hel_\n"
+        );
+        assert_eq!(
+            dump(Charset::Latin1, b"\xe9\xad\x7f!"),
+            "00000000: e9ad 7f21  é__!\n"
+        );
+        assert_eq!(
+            dump(Charset::Cp437, b"\x01\xb0\xff\x00"),
+            "00000000: 01b0 ff00  ☺░__\n"
+        );
+    }
+
+    #[test]
+    fn test_utf8_dump_across_lines() {
+        let config = Config {
+            cols: 4,
+            charset: Charset::Utf8,
+            input: Input::Stream(Box::new("añ世界😀".as_bytes())),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_with(config).unwrap(),
+            "00000000: 61c3 b1e4  añ·世\n\
+             00000004: b896 e795  ··界\n\
+             00000008: 8cf0 9f98  ·😀·\n\
+             0000000c: 80         ·\n"
+        );
+    }
+
+    #[test]
+    fn test_utf8_dump_invalid_and_wide_at_line_end() {
+        let config = Config {
+            cols: 3,
+            charset: Charset::Utf8,
+            input: Input::Stream(Box::new(b"\xffa\xe4\xb8\x96\n\xe4\xb8" as &[u8])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_with(config).unwrap(),
+            "00000000: ff61 e4  �a世\n\
+             00000003: b896 0a  ··.\n\
+             00000006: e4b8     ��\n"
+        );
+    }
+
+    #[test]
+    fn test_utf8_dump_single_column() {
+        let config = Config {
+            cols: 1,
+            charset: Charset::Utf8,
+            input: Input::Stream(Box::new("ñ世!".as_bytes())),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_with(config).unwrap(),
+            "00000000: c3  ñ\n\
+             00000001: b1  ·\n\
+             00000002: e4  世\n\
+             00000003: b8  ·\n\
+             00000004: 96  ·\n\
+             00000005: 21  !\n"
+        );
+    }
+
+    #[test]
+    fn test_utf16_dump() {
+        let dump = |charset, input: &'static [u8]| {
+            dump_with(Config {
+                cols: 5,
+                charset,
+                input: Input::Stream(Box::new(input)),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            dump(Charset::Utf16Le, b"A\x00\x16\x4e\x3d\xd8\x00\xde\x00\xd8!"),
+            "00000000: 4100 164e 3d  A·世😀\n\
+             00000005: d800 de00 d8  ···��\n\
+             0000000a: 21            �\n"
+        );
+        assert_eq!(
+            dump(Charset::Utf16Be, b"\x00A\x4e\x16"),
+            "00000000: 0041 4e16     A·世\n"
+        );
+    }
+
+    #[test]
+    fn test_autoskip_dump() {
+        let mut input = vec![0u8; 0x48];
+        input[0] = b'A';
+
+        let output = dump_with(Config {
+            autoskip: true,
+            input: Input::Stream(Box::new(Cursor::new(input))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: 4100 0000 0000 0000 0000 0000 0000 0000  A...............\n\
+             00000010: 0000 0000 0000 0000 0000 0000 0000 0000  ................\n\
+             *\n\
+             00000040: 0000 0000 0000 0000                      ........\n"
+        );
+    }
+
+    #[test]
+    fn test_squeeze_dump_shows_last_line() {
+        let output = dump_with(Config {
+            cols: 4,
+            squeeze: true,
+            input: Input::Stream(Box::new(Cursor::new(b"ABCDABCDABCDABCDwxyzwxyz"))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "00000000: 4142 4344  ABCD\n\
+             *\n\
+             00000010: 7778 797a  wxyz\n\
+             00000014: 7778 797a  wxyz\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_expands_skipped_lines() {
+        let output = SharedBuf::new(&[0xffu8; 20]);
+        let input = "00000000: 4142 4344  ABCD\n*\n00000010: 7778 797a  wxyz\n";
+
+        reverse(input, Output::Seekable(Box::new(output.clone())), None).unwrap();
+
+        assert_eq!(output.contents(), b"ABCDABCDABCDABCDwxyz");
+    }
+
+    #[test]
+    fn test_reverse_trailing_skip_marker() {
+        let input = "00000000: 4142 4344  ABCD\n*\n";
+        let result = reverse(input, Output::Stream(Box::new(io::sink())), None);
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("must be followed by a line")
+        );
+    }
+
+    #[test]
+    fn test_include_dump() {
+        let output = dump_with(Config {
+            format: Format::Include,
+            cols: 12,
+            name: Some(String::from("3rd/hello.txt")),
+            input: Input::Stream(Box::new(Cursor::new(HELLO))),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "unsigned char __3rd_hello_txt[] = {\n\
+             \x20 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x2c, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,\n\
+             \x20 0x21, 0x20, 0x54, 0x68, 0x69, 0x73, 0x20, 0x69, 0x73, 0x20, 0x68, 0x78,\n\
+             \x20 0x78, 0x2e\n\
+             };\n\
+             unsigned int __3rd_hello_txt_len = 26;\n"
+        );
+    }
+
+    #[test]
+    fn test_include_dump_empty_capitalized() {
+        let output = dump_with(Config {
+            format: Format::Include,
+            name: Some(String::from("empty.bin")),
+            capitalize: true,
+            input: Input::Stream(Box::new(io::empty())),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "unsigned char EMPTY_BIN[] = {\n};\nunsigned int EMPTY_BIN_LEN = 0;\n"
+        );
+    }
+
+    #[test]
+    fn test_include_reverse() {
+        let config = Config {
+            format: Format::Include,
+            ..Default::default()
+        };
+
+        let with_declarations = "unsigned char hello[] = {\n  0x48, 0x65,\n  0x6c, 0x6C, 0x6f\n};\n\
+                                 unsigned int hello_len = 5;\n";
+        let output = SharedBuf::default();
+        reverse_with(
+            with_declarations,
+            Output::Stream(Box::new(output.clone())),
+            config,
+        )
+        .unwrap();
+        assert_eq!(output.contents(), b"Hello");
+
+        let config = Config {
+            format: Format::Include,
+            ..Default::default()
+        };
+
+        let bare = "  0x48, 0x65,\n  0x6c, 0x6c, 0x6f\n";
+        let output = SharedBuf::default();
+        reverse_with(bare, Output::Stream(Box::new(output.clone())), config).unwrap();
+        assert_eq!(output.contents(), b"Hello");
+    }
+
+    #[test]
+    fn test_reverse_fills_gaps_on_stream() {
+        let output = SharedBuf::default();
+        let input = "00000004: 4242  BB\n00000010: 4343  CC\n";
+
+        reverse(input, Output::Stream(Box::new(output.clone())), None).unwrap();
+
+        let mut expected = vec![0u8; 18];
+        expected[4..6].copy_from_slice(b"BB");
+        expected[16..18].copy_from_slice(b"CC");
+
+        assert_eq!(output.contents(), expected);
+    }
+
+    #[test]
+    fn test_reverse_patches_seekable_in_place() {
+        let output = SharedBuf::new(b"AAAAAAAAAAAAAAAAAAAA");
+        let input = "00000008: 4242  BB\n00000002: 4343  CC\n";
+
+        reverse(input, Output::Seekable(Box::new(output.clone())), None).unwrap();
+
+        assert_eq!(output.contents(), b"AACCAAAABBAAAAAAAAAA");
+    }
+
+    #[test]
+    fn test_reverse_with_displacement() {
+        let output = SharedBuf::new(b"AAAAAAAA");
+        let input = "00000010: 4242  BB\n";
+
+        let seek = Some(SeekFrom::End(-0x0c));
+        reverse(input, Output::Seekable(Box::new(output.clone())), seek).unwrap();
+
+        assert_eq!(output.contents(), b"AAAABBAA");
+    }
+
+    #[test]
+    fn test_reverse_stream_cannot_seek_backwards() {
+        let output = SharedBuf::default();
+        let input = "00000010: 4242  BB\n00000002: 4343  CC\n";
+
+        let result = reverse(input, Output::Stream(Box::new(output)), None);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("cannot seek backwards")
+        );
+    }
+
+    #[test]
+    fn test_missing_colon() {
+        let input = Cursor::new("00000000  48 65 6c 6c 6f 20 77 6f  72 6c 64\n");
+        let output = Cursor::new(Vec::new());
+
+        let config = Config {
+            cols: 16,
+            byte_groups: 2,
+            reverse: true,
+            input: Input::Stream(Box::new(input)),
+            output: Output::Stream(Box::new(output)),
+            ..Default::default()
+        };
+
+        let result = reverse_hex_dump(config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("missing ':'"));
+    }
+
+    #[test]
+    fn test_missing_double_space_separator() {
+        let input = Cursor::new("00000000: 48 65 6c 6c 6f 20 776f726c64\n");
+        let output = Cursor::new(Vec::new());
+
+        let config = Config {
+            cols: 16,
+            byte_groups: 2,
+            reverse: true,
+            input: Input::Stream(Box::new(input)),
+            output: Output::Stream(Box::new(output)),
+            ..Default::default()
+        };
+
+        let result = reverse_hex_dump(config);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("missing double space separator")
+        );
+    }
+
+    #[test]
+    fn test_line_too_short() {
+        let input = Cursor::new("00000000: 48\n");
+        let output = Cursor::new(Vec::new());
+
+        let config = Config {
+            cols: 16,
+            byte_groups: 2,
+            reverse: true,
+            input: Input::Stream(Box::new(input)),
+            output: Output::Stream(Box::new(output)),
+            ..Default::default()
+        };
+
+        let result = reverse_hex_dump(config);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("missing double space separator")
+        );
+    }
+
+    #[test]
+    fn test_odd_number_of_hex_digits() {
+        let input = Cursor::new("00000000: 4 8 6 5 6 c 6 c 6 f 2 0 7 7 6 f 7 2 6 c 6  \n");
+        let output = Cursor::new(Vec::new());
+
+        let config = Config {
+            cols: 16,
+            byte_groups: 2,
+            reverse: true,
+            input: Input::Stream(Box::new(input)),
+            output: Output::Stream(Box::new(output)),
+            ..Default::default()
+        };
+
+        let result = reverse_hex_dump(config);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("odd number of hex digits")
+        );
+    }
+
+    #[test]
+    fn test_invalid_hex_character() {
+        let input = Cursor::new("00000000: 48 65 6c 6c 6f 2G 77 6f 72 6c 64  \n");
+        let output = Cursor::new(Vec::new());
+
+        let config = Config {
+            cols: 16,
+            byte_groups: 2,
+            reverse: true,
+            input: Input::Stream(Box::new(input)),
+            output: Output::Stream(Box::new(output)),
+            ..Default::default()
+        };
+
+        let result = reverse_hex_dump(config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("invalid hex char"));
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let input = "00000000: 4865 6c6c  Hell\n00000004: 6f2G 7772  o,wr\n";
+        let result = reverse(input, Output::Stream(Box::new(io::sink())), None);
+
+        match result.unwrap_err() {
+            Error::Parse {
+                line, column, text, ..
+            } => {
+                assert_eq!((line, column), (2, 14));
+                assert_eq!(text, "00000004: 6f2G 7772  o,wr");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_at_end_of_input() {
+        let config = Config {
+            format: Format::Plain,
+            ..Default::default()
+        };
+
+        let result = reverse_with("4865\n6c6\n", Output::Stream(Box::new(io::sink())), config);
+        let err = result.unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "malformed hex: odd number of hex digits (line 2, column 4)"
+        );
+    }
+
+    #[test]
+    fn test_reverse_invalid_utf8_text_column() {
+        // Text columns written with other charsets, or edited by hand, need not be UTF-8
+        let input = b"00000000: 48e9 ff6c  H\xe9\xff\xfe\n00000004: 6f    \xc3\n".to_vec();
+        let output = SharedBuf::default();
+
+        let config = Config {
+            reverse: true,
+            input: Input::Stream(Box::new(Cursor::new(input))),
+            output: Output::Stream(Box::new(output.clone())),
+            ..Default::default()
+        };
+
+        reverse_hex_dump(config).unwrap();
+        assert_eq!(output.contents(), b"H\xe9\xffl\x6f");
+
+        // Invalid bytes before a problem count as one column each
+        let input = b"\xff00000000: 48\n".to_vec();
+
+        let config = Config {
+            reverse: true,
+            input: Input::Stream(Box::new(Cursor::new(input))),
+            output: Output::Stream(Box::new(io::sink())),
+            ..Default::default()
+        };
+
+        match reverse_hex_dump(config).unwrap_err() {
+            Error::Parse { column, text, .. } => {
+                assert_eq!(column, 1);
+                assert_eq!(text, "\u{fffd}00000000: 48");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_io_error_source() {
+        let input = Input::Stream(Box::new(Cursor::new(HELLO)));
+        let err = dump(input, Some(SeekFrom::End(-5)), None).unwrap_err();
+
+        assert!(matches!(err, Error::Io { .. }));
+        assert!(std::error::Error::source(&err).is_some());
     }
 }
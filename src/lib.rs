@@ -4,13 +4,59 @@
 //!
 //! # Features
 //! - Generate hex dumps from files or `stdin`, with output directed to a file or `stdout`.
+//!   Lines are always complete, even when reading from pipes or terminals.
 //! - Customize hex dump formatting, including column width and byte grouping.
-//! - Rebuild original binary data from hex dump input.
+//! - GNU-style command-line options: long options, clustered flags, `--`, and `-` for `stdin`.
+//! - Start at an arbitrary input position and limit the number of bytes dumped.
+//! - Plain hex output without offsets or ASCII representation.
+//! - C include file output for embedding binary data as an array.
+//! - Binary digit output for inspecting individual bits.
+//! - Little-endian output of byte groups.
+//! - Skipping of nul or repeated lines.
+//! - Colored output by byte class.
+//! - Text column in EBCDIC, Latin-1 or CP437, with a configurable placeholder character.
+//! - UTF-8 and UTF-16 text column, decoding characters across line boundaries.
+//! - Hex dumps replace output files atomically, never leaving them half-written, while reverse
+//!   patches and appends (`--append`) write to existing files in place.
+//! - Rebuild original binary data from hex dump input, or patch existing files in place.
+//! - `Dumper` builder to format hex dumps from any reader, byte slice or single line, without
+//!   going through `Config`.
+//! - Lazy iterator over the lines of a hex dump as `DumpLine` records (offset, bytes, hex and
+//!   text), for custom rendering.
+//! - `HexEncoder` and `HexDecoder` stream adapters, implementing `Write` and `Read`.
+//! - Table-driven formatting engine, writing lines in large batches (see `examples/bench.rs`).
+//! - Byte-level reverse parsing, accepting any bytes (even invalid UTF-8) in the text column.
+//! - Configurable offset width, or fitted to the input size (`-w auto`), keeping columns
+//!   aligned past 4 GiB; offsets of any width up to `u64::MAX` are parsed back.
+//! - Multithreaded formatting and reverse parsing (`-j`), with output identical to a single
+//!   thread.
+//! - Hex dumps of several files at once (`-m`), each after a header with its name and size,
+//!   with offsets restarting or continuing across files; reverse recreates every file.
+//! - Structured errors, locating malformed hex dump input by line and column.
+//!
+//! The library never prints or exits on its own: command-line parsing returns an `Action`,
+//! leaving help and version output to the caller.
 
 #![warn(missing_docs)]
 
+mod atomic;
+mod charset;
+mod color;
 mod config;
+mod dumper;
+mod error;
 mod hex;
+mod multi;
+mod parallel;
+mod stream;
+#[cfg(test)]
+mod test_util;
 
-pub use config::{Config, print_usage, print_version};
+pub use atomic::AtomicFile;
+pub use charset::Charset;
+pub use config::{Action, Config, Format, Input, Output, ReadSeek, WriteSeek, usage, version};
+pub use dumper::{DumpLine, DumpLines, Dumper};
+pub use error::Error;
 pub use hex::{hex_dump, reverse_hex_dump, run};
+pub use multi::{hex_dump_files, reverse_hex_dump_files};
+pub use stream::{HexDecoder, HexEncoder};
@@ -1,24 +1,41 @@
 use std::env;
 use std::process;
 
-use hxx::{Config, print_usage, run};
+use hxx::{Action, Config, Error, run, usage};
 
 fn main() {
-    let mut args = env::args();
-    let program = args.next().unwrap_or_else(|| {
-        eprintln!("\x1b[1;91mError: invalid or missing program\x1b[0m");
-        process::exit(1);
-    });
+    // Paths are not required to be valid UTF-8
+    let mut args = env::args_os();
+    let program = args
+        .next()
+        .map(|program| program.to_string_lossy().into_owned())
+        .unwrap_or_else(|| {
+            eprintln!("\x1b[1;91mError: invalid or missing program\x1b[0m");
+            process::exit(1);
+        });
 
-    let config = Config::build(args, &program).unwrap_or_else(|err| {
-        eprintln!("\x1b[1;91mERROR: {err}\x1b[0m");
-        // Will terminate program with exit code (1)
-        print_usage(&program);
-        unreachable!();
-    });
+    let config = match Config::build(args, &program) {
+        Ok(Action::Run(config)) => *config,
+        Ok(Action::Help(text) | Action::Version(text)) => {
+            println!("{text}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("\x1b[1;91mERROR: {err}\x1b[0m");
+            eprintln!("{}", usage(&program));
+            process::exit(1);
+        }
+    };
 
     if let Err(err) = run(config) {
         eprintln!("\x1b[1;91mERROR: {err}\x1b[0m");
+
+        // Point at the problem in the offending line of a malformed hex dump
+        if let (Error::Parse { line, text, .. }, Some(caret)) = (&err, err.caret()) {
+            eprintln!("{line:>6} | {text}");
+            eprintln!("{:>6} | {caret}", "");
+        }
+
         process::exit(1);
     }
 }
@@ -0,0 +1,569 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::dumper::BLOCK_SIZE;
+use crate::hex::Reverser;
+use crate::{AtomicFile, Config, Error, Output};
+
+/// Generates a hex dump of several files, one after another, using the provided `Config`.
+///
+/// Each of `files` is dumped like `hex_dump` would, after a header line with its name and size
+/// in bytes (`# <name> (<size> bytes)`). Names are the last component of each path, so that
+/// `reverse_hex_dump_files` can recreate the files in any directory, and must all differ.
+///
+/// Offsets restart at 0 (plus the display offset) for each file, or continue from one file to
+/// the next if `continuous` is set. Runs of lines skipped by `autoskip` or `squeeze` never span
+/// two files, and `offset_width` set to `None` (`auto`) fits the offsets of every file.
+///
+/// # Example
+///
+/// ```no_run
+/// let config = hxx::Config {
+///     multi: true,
+///     files: vec!["boot.bin".into(), "kernel.bin".into()],
+///     ..Default::default()
+/// };
+///
+/// if let Err(err) = hxx::hex_dump_files(config) {
+///     eprintln!("Error: {err}");
+///     std::process::exit(1);
+/// }
+/// ```
+///
+/// # Error
+///
+/// This function returns an error if:
+/// - A file cannot be opened or read.
+/// - A path does not end with a file name, or two paths end with the same one.
+/// - The name of a file holds a line break, which would break its header.
+/// - It fails to write to the output stream.
+pub fn hex_dump_files(config: Config) -> Result<(), Error> {
+    let dumper = config.dumper();
+    let mut output = config.output;
+
+    // Names are checked first, as files that cannot tell their size are read when opened
+    let names = config
+        .files
+        .iter()
+        .map(|path| file_name(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut seen = HashSet::new();
+
+    if let Some(name) = names.iter().find(|name| !seen.insert(*name)) {
+        return Err(Error::usage(format!(
+            "several files are named {name:?}, which would overwrite each other"
+        )));
+    }
+
+    // Sizes are shown before the contents, so files that cannot tell their size are read first
+    let sources = config
+        .files
+        .iter()
+        .map(|path| Source::open(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let dumper = match config.offset_width {
+        Some(_) => dumper,
+        None => {
+            let sizes = sources.iter().map(|source| source.size);
+
+            let len = match config.continuous {
+                true => sizes.fold(0, u64::saturating_add),
+                false => sizes.max().unwrap_or(0),
+            };
+
+            let offset_width = dumper.offset_width_for(len);
+            dumper.offset_width(offset_width)
+        }
+    };
+
+    let mut base = 0u64;
+
+    for (name, source) in names.iter().zip(sources) {
+        writeln!(output, "# {name} ({} bytes)", source.size)
+            .map_err(Error::io("failed to write to output"))?;
+
+        let display_offset = match config.continuous {
+            true => config.display_offset.wrapping_add(base),
+            false => config.display_offset,
+        };
+
+        // C include arrays are named after their file, like the input of `hex_dump`
+        let dumper = dumper.clone().display_offset(display_offset);
+        let dumper = match &config.name {
+            Some(_) => dumper,
+            None => dumper.name(source.path.to_string_lossy()),
+        };
+
+        dumper.dump(source.reader.take(source.size), &mut output)?;
+
+        base = base.wrapping_add(source.size);
+    }
+
+    // Output files are only replaced once everything was written
+    output
+        .finish()
+        .map_err(Error::io("failed to write to output"))?;
+
+    Ok(())
+}
+
+/// Recreates the files of a hex dump made by `hex_dump_files`, using the given `Config`.
+///
+/// The lines following each header are decoded like `reverse_hex_dump` would, into the file
+/// named by the header, created in `directory` (or the current directory), along with its
+/// missing parent directories. Offsets are expected to restart at 0 (plus the display offset)
+/// for each file, or to continue from one file to the next if `continuous` is set.
+///
+/// Files are replaced once complete, never left half-written. Only relative names without
+/// `..` components are accepted, so that no file outside of `directory` is ever written.
+/// Lines are parsed on the calling thread, whatever `jobs`.
+///
+/// # Example
+///
+/// ```no_run
+/// let Ok(input) = std::fs::File::open("firmware.hex") else {
+///     return;
+/// };
+///
+/// let config = hxx::Config {
+///     reverse: true,
+///     multi: true,
+///     directory: Some("firmware".into()),
+///     input: hxx::Input::Stream(Box::new(input)),
+///     ..Default::default()
+/// };
+///
+/// if let Err(err) = hxx::reverse_hex_dump_files(config) {
+///     eprintln!("Error: {err}");
+///     std::process::exit(1);
+/// }
+/// ```
+///
+/// # Error
+///
+/// This function returns an error if:
+/// - It fails to read from the input stream.
+/// - A file or directory cannot be created or written.
+/// - Lines are found before the first header.
+/// - A header is malformed, or names a file outside of `directory`.
+/// - The bytes decoded for a file do not match the size given by its header.
+/// - The input data is otherwise malformed, as for `reverse_hex_dump`.
+pub fn reverse_hex_dump_files(config: Config) -> Result<(), Error> {
+    let dumper = config.dumper();
+    let directory = config.directory.unwrap_or_default();
+
+    let mut reader = BufReader::with_capacity(BLOCK_SIZE, config.input);
+
+    let mut file: Option<FileSection> = None;
+    let mut base = 0u64;
+
+    // Lines are kept as raw bytes, so the text column may hold anything
+    let mut buf = Vec::with_capacity(1024);
+    let mut line_number = 0;
+
+    loop {
+        // Reset buffer since `read_until()` preserves buffer contents
+        buf.clear();
+
+        let bytes_read = reader
+            .read_until(b'\n', &mut buf)
+            .map_err(Error::io("failed to read from input"))?;
+
+        // Check for EOF
+        if bytes_read == 0 {
+            break;
+        }
+
+        line_number += 1;
+
+        let Some((name, size)) = parse_header(&buf, line_number)? else {
+            match &mut file {
+                Some(file) => file.reverser.decode_line(&buf, &mut file.writer)?,
+                // Nothing but blank lines may come before the first file
+                None if buf.trim_ascii().is_empty() => {}
+                None => {
+                    return Err(Error::parse(
+                        line_number,
+                        &buf,
+                        0,
+                        "malformed line: missing file header",
+                    ));
+                }
+            }
+
+            continue;
+        };
+
+        if let Some(file) = file.take() {
+            file.finish()?;
+        }
+
+        let path = file_path(&directory, &name).ok_or_else(|| {
+            Error::parse(
+                line_number,
+                &buf,
+                2,
+                "malformed header: file name must be relative, without '..'",
+            )
+        })?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::io("failed to create directory"))?;
+        }
+
+        let output =
+            AtomicFile::create(&path, false).map_err(Error::io("failed to create file"))?;
+
+        let display_offset = match config.continuous {
+            true => config.display_offset.wrapping_add(base),
+            false => config.display_offset,
+        };
+
+        file = Some(FileSection {
+            reverser: Reverser::new(&dumper.clone().display_offset(display_offset), 0)
+                .start_at_line(line_number),
+            writer: BufWriter::with_capacity(BLOCK_SIZE, Output::Atomic(output)),
+            header: buf.clone(),
+            line_number,
+            size,
+        });
+
+        base = base.wrapping_add(size);
+    }
+
+    if let Some(file) = file {
+        file.finish()?;
+    }
+
+    Ok(())
+}
+
+// File to dump, with its size known upfront
+struct Source<'a> {
+    path: &'a Path,
+    size: u64,
+    reader: Box<dyn Read>,
+}
+
+impl<'a> Source<'a> {
+    fn open(path: &'a Path) -> Result<Self, Error> {
+        let mut file = File::open(path).map_err(Error::io("failed to open file"))?;
+
+        // Pipes, character devices, etc. can only tell their size once read
+        let (size, reader): (_, Box<dyn Read>) = match file.metadata() {
+            Ok(metadata) if metadata.is_file() => (metadata.len(), Box::new(file)),
+            _ => {
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents)
+                    .map_err(Error::io("failed to read from input"))?;
+
+                (contents.len() as u64, Box::new(Cursor::new(contents)))
+            }
+        };
+
+        Ok(Self { path, size, reader })
+    }
+}
+
+// File being recreated from the lines following its header
+struct FileSection {
+    reverser: Reverser,
+    writer: BufWriter<Output>,
+    // Header line, to locate a size mismatch
+    header: Vec<u8>,
+    line_number: usize,
+    size: u64,
+}
+
+impl FileSection {
+    fn finish(mut self) -> Result<(), Error> {
+        self.reverser.finish()?;
+
+        if self.reverser.position() != self.size {
+            let index = self.header.trim_ascii_end().len();
+
+            return Err(Error::parse(
+                self.line_number,
+                &self.header,
+                index,
+                "malformed header: size does not match the hex dump",
+            ));
+        }
+
+        self.writer
+            .flush()
+            .map_err(Error::io("failed to write to output"))?;
+
+        // Files are only replaced once everything was written
+        self.writer
+            .into_inner()
+            .map_err(|err| Error::io("failed to write to output")(err.into_error()))?
+            .finish()
+            .map_err(Error::io("failed to write to output"))?;
+
+        Ok(())
+    }
+}
+
+// Name of `path` in a header: its last component, which can be recreated in any directory
+fn file_name(path: &Path) -> Result<String, Error> {
+    let Some(name) = path.file_name() else {
+        return Err(Error::usage(format!(
+            "path does not end with a file name: {path:?}"
+        )));
+    };
+
+    let name = name.to_string_lossy();
+
+    if name.contains(['\n', '\r']) {
+        return Err(Error::usage(format!(
+            "file name cannot hold a line break: {name:?}"
+        )));
+    }
+
+    Ok(name.into_owned())
+}
+
+// Path of the file named `name` in `directory`, unless it would lead outside of `directory`
+fn file_path(directory: &Path, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+
+    let inside = name.file_name().is_some()
+        && name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    inside.then(|| directory.join(name))
+}
+
+// Parses a `# <name> (<size> bytes)` header line into the name and size of a file, or returns
+// `None` for any other line
+fn parse_header(buf: &[u8], line_number: usize) -> Result<Option<(String, u64)>, Error> {
+    let Some(header) = buf.strip_prefix(b"# ") else {
+        return Ok(None);
+    };
+
+    let malformed = || {
+        Error::parse(
+            line_number,
+            buf,
+            buf.trim_ascii_end().len(),
+            "malformed header: expected '# <name> (<size> bytes)'",
+        )
+    };
+
+    let header = String::from_utf8_lossy(header);
+    let header = header.trim_end_matches(['\r', '\n']);
+
+    let (name, size) = header.rsplit_once(" (").ok_or_else(malformed)?;
+
+    let size = size
+        .strip_suffix(" bytes)")
+        .and_then(|size| size.parse::<u64>().ok())
+        .ok_or_else(malformed)?;
+
+    Ok(Some((name.to_string(), size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Format, Input};
+
+    use std::io;
+
+    use crate::test_util::{SharedBuf, test_dir};
+
+    fn dump_files(config: Config) -> String {
+        let output = SharedBuf::default();
+
+        hex_dump_files(Config {
+            multi: true,
+            output: Output::Stream(Box::new(output.clone())),
+            ..config
+        })
+        .unwrap();
+
+        String::from_utf8(output.contents()).unwrap()
+    }
+
+    fn reverse_files(dump: &str, config: Config) -> Result<(), Error> {
+        reverse_hex_dump_files(Config {
+            reverse: true,
+            multi: true,
+            input: Input::Stream(Box::new(Cursor::new(dump.to_string()))),
+            ..config
+        })
+    }
+
+    #[test]
+    fn test_dump_files_with_headers() {
+        let dir = test_dir("multi-dump");
+        fs::write(dir.join("a.bin"), b"Hello, world!").unwrap();
+        fs::write(dir.join("b.bin"), b"hxx").unwrap();
+
+        let files = vec![dir.join("a.bin"), dir.join("b.bin")];
+
+        let config = || Config {
+            cols: 8,
+            files: files.clone(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_files(config()),
+            "# a.bin (13 bytes)\n\
+             00000000: 4865 6c6c 6f2c 2077  Hello, w\n\
+             00000008: 6f72 6c64 21         orld!\n\
+             # b.bin (3 bytes)\n\
+             00000000: 6878 78              hxx\n"
+        );
+
+        let output = dump_files(Config {
+            continuous: true,
+            ..config()
+        });
+
+        assert!(output.ends_with("(3 bytes)\n0000000d: 6878 78              hxx\n"));
+
+        // Names must tell the files apart, and be recreated inside of any directory
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/a.bin"), b"hxx").unwrap();
+
+        for (files, message) in [
+            (
+                vec![dir.join("a.bin"), dir.join("sub/a.bin")],
+                "several files",
+            ),
+            (vec![dir.join("sub/..")], "does not end with a file name"),
+        ] {
+            let Err(Error::Usage(found)) = hex_dump_files(Config {
+                multi: true,
+                files,
+                output: Output::Stream(Box::new(io::sink())),
+                ..Default::default()
+            }) else {
+                panic!("expected a usage error for {message:?}");
+            };
+
+            assert!(found.contains(message), "{found}");
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reverse_files_round_trip() {
+        let dir = test_dir("multi-round-trip");
+        fs::create_dir_all(dir.join("fw")).unwrap();
+        fs::write(dir.join("fw/boot.bin"), [0u8; 100]).unwrap();
+        fs::write(dir.join("fw/empty.bin"), b"").unwrap();
+        fs::write(dir.join("kernel.bin"), b"Hello, world! This is hxx.").unwrap();
+
+        for (format, continuous) in [
+            (Format::Normal, false),
+            (Format::Normal, true),
+            (Format::Plain, false),
+            (Format::Include, false),
+            (Format::Bits, true),
+        ] {
+            let config = || Config {
+                cols: format.default_cols(),
+                byte_groups: format.default_byte_groups(),
+                format,
+                autoskip: true,
+                continuous,
+                ..Default::default()
+            };
+
+            // Files are recreated by name only, out of their directories
+            let dump = dump_files(Config {
+                files: vec![
+                    dir.join("fw/boot.bin"),
+                    dir.join("fw/empty.bin"),
+                    dir.join("kernel.bin"),
+                ],
+                ..config()
+            });
+
+            let out = dir.join("out");
+            reverse_files(
+                &dump,
+                Config {
+                    directory: Some(out.clone()),
+                    ..config()
+                },
+            )
+            .unwrap();
+
+            for file in ["fw/boot.bin", "fw/empty.bin", "kernel.bin"] {
+                let name = Path::new(file).file_name().unwrap();
+
+                assert_eq!(
+                    fs::read(out.join(name)).unwrap(),
+                    fs::read(dir.join(file)).unwrap(),
+                    "{format:?} {file}"
+                );
+            }
+
+            fs::remove_dir_all(out).unwrap();
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reverse_files_errors() {
+        let dir = test_dir("multi-errors");
+        let config = || Config {
+            directory: Some(dir.clone()),
+            ..Default::default()
+        };
+
+        for (dump, line, message) in [
+            ("00000000: 6869  hi\n", 1, "missing file header"),
+            ("# a.bin\n", 1, "expected '# <name> (<size> bytes)'"),
+            (
+                "# ../a.bin (2 bytes)\n00000000: 6869  hi\n",
+                1,
+                "must be relative",
+            ),
+            (
+                "# /a.bin (2 bytes)\n00000000: 6869  hi\n",
+                1,
+                "must be relative",
+            ),
+            (
+                "# a.bin (3 bytes)\n00000000: 6869  hi\n",
+                1,
+                "size does not match",
+            ),
+            (
+                "# a.bin (2 bytes)\n00000000: 6869  hi\n# b.bin (1 bytes)\n0000000g: 68  h\n",
+                4,
+                "invalid offset",
+            ),
+        ] {
+            let Err(Error::Parse {
+                line: found,
+                message: found_message,
+                ..
+            }) = reverse_files(dump, config())
+            else {
+                panic!("expected a parse error for {dump:?}");
+            };
+
+            assert_eq!(found, line, "{dump:?}");
+            assert!(found_message.contains(message), "{found_message}");
+        }
+
+        // Files are only replaced once complete
+        assert!(dir.join("a.bin").exists());
+        assert!(!dir.join("b.bin").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,448 @@
+use std::io::{BufRead, Read, Write};
+use std::ops::Range;
+use std::panic;
+use std::thread;
+
+use crate::dumper::{DumpState, LineContext, LineReader, head, tail};
+use crate::hex::{Patch, Reverser};
+use crate::{Dumper, Error};
+
+// Amount of input handled by a thread at a time. Jobs end on line boundaries, so they are
+// slightly larger
+#[cfg(not(test))]
+const JOB_SIZE: usize = 1 << 20;
+
+// Small enough for tests to cross many job boundaries
+#[cfg(test)]
+const JOB_SIZE: usize = 40;
+
+// Lines of the data to dump, formatted by a thread on its own, along with the bytes around
+// them that their formatting depends on
+struct DumpJob {
+    // Position of the first line in the data
+    offset: u64,
+    // Bytes before the first line, to decode characters crossing its boundary
+    before: Vec<u8>,
+    data: Vec<u8>,
+    // Number of lines following `data` identical to its last line, all of them skipped
+    repeats: u64,
+    // Line following the job (never skipped), and the bytes after it, or nothing at the end
+    // of input
+    next: Vec<u8>,
+    ahead: Vec<u8>,
+}
+
+impl DumpJob {
+    fn new(bytes: &[u8], context: LineContext) -> Self {
+        let mut data = Vec::with_capacity(JOB_SIZE + bytes.len());
+        data.extend_from_slice(bytes);
+
+        Self {
+            offset: context.index,
+            before: context.before.to_vec(),
+            data,
+            repeats: 0,
+            next: Vec::new(),
+            ahead: Vec::new(),
+        }
+    }
+
+    fn last_line(&self, cols: usize) -> &[u8] {
+        &self.data[(self.data.len() - 1) / cols * cols..]
+    }
+
+    // Formats the lines of the job exactly as they are formatted when dumping the whole data
+    fn format(&self, dumper: &Dumper) -> Result<Vec<u8>, Error> {
+        let mut state = DumpState::new(dumper);
+        let cols = state.line_format().cols;
+        let context_len = state.line_format().charset.context_len();
+
+        let mut output = Vec::with_capacity(self.data.len() * 5);
+
+        state.resume(self.offset);
+
+        if self.offset == 0 {
+            state.start();
+        }
+
+        // Bytes following the lines of the job, as far as they are needed to decode
+        // characters crossing the boundary of the last line
+        let mut after = Vec::with_capacity(context_len + self.next.len() + self.ahead.len());
+
+        for _ in 0..self.repeats.min(context_len as u64) {
+            after.extend_from_slice(self.last_line(cols));
+        }
+
+        after.extend_from_slice(&self.next);
+        after.extend_from_slice(&self.ahead);
+
+        let window = [&self.before[..], &self.data, &after].concat();
+        let start = self.before.len();
+
+        for (i, bytes) in self.data.chunks(cols).enumerate() {
+            let line_start = start + i * cols;
+            let line_end = line_start + bytes.len();
+
+            let context = LineContext {
+                index: self.offset + (i * cols) as u64,
+                before: tail(&window[..line_start], context_len),
+                after: head(&window[line_end..], context_len),
+            };
+
+            state.push(&mut output, bytes, context)?;
+        }
+
+        state.repeat(self.repeats);
+
+        if self.next.is_empty() {
+            state.end(&mut output)?;
+        } else {
+            let next_offset = self.offset
+                + self.data.len() as u64
+                + self.repeats * self.last_line(cols).len() as u64;
+
+            state.close_run(next_offset, &self.next);
+            state.flush(&mut output)?;
+        }
+
+        Ok(output)
+    }
+}
+
+// Dumps everything read from `reader` to `writer` like `Dumper::dump`, formatting the lines
+// with `jobs` threads. Input is split into jobs of whole lines, which never start with a
+// skipped line, so that runs of skipped lines are handled by a single thread
+pub(crate) fn dump(
+    dumper: &Dumper,
+    jobs: usize,
+    reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let mut state = DumpState::new(dumper);
+    let cols = state.line_format().cols;
+    let context_len = state.line_format().charset.context_len();
+
+    let mut lines = LineReader::new(reader, cols, context_len);
+    let mut batch: Vec<DumpJob> = Vec::with_capacity(jobs);
+    let mut job: Option<DumpJob> = None;
+
+    while lines
+        .read_line()
+        .map_err(Error::io("failed to read from input"))?
+        > 0
+    {
+        let bytes = lines.bytes();
+        let context = lines.context();
+
+        match &mut job {
+            Some(current) if current.data.len() < JOB_SIZE => {
+                current.data.extend_from_slice(bytes);
+            }
+            Some(current) if state.is_repeat(current.last_line(cols), bytes) => {
+                current.repeats += 1;
+            }
+            _ => {
+                if let Some(mut current) = job.take() {
+                    current.next = bytes.to_vec();
+                    current.ahead = context.after.to_vec();
+
+                    batch.push(current);
+
+                    if batch.len() == jobs {
+                        dump_batch(dumper, &mut batch, &mut writer)?;
+                    }
+                }
+
+                job = Some(DumpJob::new(bytes, context));
+            }
+        }
+    }
+
+    batch.extend(job);
+
+    if batch.is_empty() {
+        // Without any line, there is nothing to split
+        state.start();
+        state.end(&mut writer)?;
+    } else {
+        dump_batch(dumper, &mut batch, &mut writer)?;
+    }
+
+    writer
+        .flush()
+        .map_err(Error::io("failed to write to output"))?;
+
+    Ok(())
+}
+
+// Formats every job of `batch` on its own thread, and writes them in order
+fn dump_batch(
+    dumper: &Dumper,
+    batch: &mut Vec<DumpJob>,
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    let outputs = thread::scope(|scope| {
+        let handles: Vec<_> = batch
+            .iter()
+            .map(|job| scope.spawn(|| job.format(dumper)))
+            .collect();
+
+        handles.into_iter().map(join).collect::<Vec<_>>()
+    });
+
+    batch.clear();
+
+    for output in outputs {
+        writer
+            .write_all(&output?)
+            .map_err(Error::io("failed to write to output"))?;
+    }
+
+    Ok(())
+}
+
+// Lines of a hex dump with offsets, once parsed by a thread on its own
+struct ParsedJob {
+    // Bytes decoded from every line
+    bytes: Vec<u8>,
+    lines: Vec<ParsedLine>,
+    // Problem found in the line following the lines parsed, which ends the hex dump
+    error: Option<Error>,
+}
+
+struct ParsedLine {
+    text: Range<usize>,
+    target: Option<u64>,
+    bytes: Range<usize>,
+}
+
+// Parses the lines of `text`, the first of which is the `first_line`-th line of the input
+fn parse_job(reverser: &Reverser, text: &[u8], first_line: usize) -> ParsedJob {
+    let mut job = ParsedJob {
+        bytes: Vec::with_capacity(text.len() / 3),
+        lines: Vec::with_capacity(text.len() / 32),
+        error: None,
+    };
+
+    let mut start = 0;
+
+    for (i, line) in text.split_inclusive(|&b| b == b'\n').enumerate() {
+        let bytes_start = job.bytes.len();
+
+        match reverser.parse_line(line, first_line + i, &mut job.bytes) {
+            Ok(target) => job.lines.push(ParsedLine {
+                text: start..start + line.len(),
+                target,
+                bytes: bytes_start..job.bytes.len(),
+            }),
+            Err(err) => {
+                job.error = Some(err);
+                break;
+            }
+        }
+
+        start += line.len();
+    }
+
+    job
+}
+
+// Reverses the hex dump read from `reader` into `output` like `Reverser::decode_line` does
+// one line at a time, parsing the lines with `jobs` threads. Lines must carry their own
+// offsets; they are then written in order, so the output is the same
+pub(crate) fn reverse(
+    reverser: &mut Reverser,
+    jobs: usize,
+    mut reader: impl BufRead,
+    output: &mut impl Patch,
+) -> Result<(), Error> {
+    let mut texts: Vec<Vec<u8>> = Vec::with_capacity(jobs);
+    let mut line_number = 1;
+
+    loop {
+        texts.clear();
+
+        while texts.len() < jobs {
+            let mut text = Vec::with_capacity(JOB_SIZE + 1024);
+
+            // Jobs end on line boundaries
+            (&mut reader)
+                .take(JOB_SIZE as u64)
+                .read_to_end(&mut text)
+                .map_err(Error::io("failed to read from input"))?;
+
+            if !text.is_empty() && !text.ends_with(b"\n") {
+                reader
+                    .read_until(b'\n', &mut text)
+                    .map_err(Error::io("failed to read from input"))?;
+            }
+
+            if text.is_empty() {
+                break;
+            }
+
+            texts.push(text);
+        }
+
+        if texts.is_empty() {
+            return Ok(());
+        }
+
+        let parsed = thread::scope(|scope| {
+            let reverser = &*reverser;
+            let mut first_line = line_number;
+
+            let handles: Vec<_> = texts
+                .iter()
+                .map(|text| {
+                    let handle = scope.spawn(move || parse_job(reverser, text, first_line));
+                    first_line += text.split_inclusive(|&b| b == b'\n').count();
+                    handle
+                })
+                .collect();
+
+            line_number = first_line;
+            handles.into_iter().map(join).collect::<Vec<_>>()
+        });
+
+        for (text, job) in texts.iter().zip(parsed) {
+            for line in &job.lines {
+                reverser.write_line(
+                    &text[line.text.clone()],
+                    line.target,
+                    &job.bytes[line.bytes.clone()],
+                    output,
+                )?;
+            }
+
+            if let Some(err) = job.error {
+                return Err(err);
+            }
+        }
+    }
+}
+
+// Waits for a thread, propagating its panic, if any
+fn join<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Charset, Format};
+
+    // Runs of nul lines, repeated lines and multi-byte characters, of lengths that are not
+    // multiples of a line or a job
+    fn data() -> Vec<u8> {
+        let mut data = b"head".to_vec();
+        data.extend([0; 97]);
+        data.extend(b"0123456789abcdef".repeat(13));
+        data.extend("ünïcödé €😀".repeat(9).bytes());
+        data.extend([0; 300]);
+        data.push(b'!');
+        data.extend((0..=255).cycle().take(333));
+        data
+    }
+
+    #[test]
+    fn test_dump_matches_single_thread() {
+        let data = data();
+
+        let dumpers = [
+            Dumper::new(),
+            Dumper::new().autoskip(true),
+            Dumper::new().squeeze(true).cols(7),
+            Dumper::new().squeeze(true).cols(1).charset(Charset::Utf8),
+            Dumper::new().autoskip(true).cols(5).charset(Charset::Utf8),
+            Dumper::new().cols(3).charset(Charset::Utf16Le),
+            Dumper::new().format(Format::Include).name("data"),
+            Dumper::new().format(Format::Plain),
+            Dumper::new().format(Format::Bits).autoskip(true),
+            Dumper::new().format(Format::LittleEndian).squeeze(true),
+            Dumper::new()
+                .color(true)
+                .autoskip(true)
+                .display_offset(0x100),
+        ];
+
+        for dumper in dumpers {
+            let expected = dumper.dump_bytes(&data);
+
+            for jobs in [2, 3, 8] {
+                assert_eq!(dumper.clone().jobs(jobs).dump_bytes(&data), expected);
+                assert_eq!(
+                    dumper.clone().jobs(jobs).dump_bytes(b""),
+                    dumper.dump_bytes(b"")
+                );
+            }
+        }
+    }
+
+    fn reverse_all(dumper: &Dumper, dump: &[u8], jobs: usize) -> Result<Vec<u8>, Error> {
+        let mut reverser = Reverser::new(dumper, 0);
+        let mut output = Vec::new();
+
+        reverse(&mut reverser, jobs, dump, &mut output)?;
+        reverser.finish()?;
+
+        Ok(output)
+    }
+
+    #[test]
+    fn test_reverse_matches_single_thread() {
+        let data = data();
+
+        let dumpers = [
+            Dumper::new(),
+            Dumper::new().squeeze(true).cols(7),
+            Dumper::new().format(Format::Bits).autoskip(true),
+            Dumper::new().format(Format::LittleEndian).squeeze(true),
+        ];
+
+        for dumper in dumpers {
+            let dump = dumper.dump_bytes(&data);
+
+            for jobs in [2, 3, 8] {
+                assert_eq!(reverse_all(&dumper, dump.as_bytes(), jobs).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reverse_reports_first_error() {
+        let dumper = Dumper::new().autoskip(true);
+        let mut dump = dumper.dump_bytes(&data());
+
+        // Lines after the first problem are never parsed by a single thread
+        dump.push_str("000003e0: 4G  .\n*\n");
+
+        let mut single = Reverser::new(&dumper, 0);
+        let mut expected = Vec::new();
+        let err = dump
+            .split_inclusive('\n')
+            .try_for_each(|line| single.decode_line(line.as_bytes(), &mut expected))
+            .unwrap_err();
+
+        let mut reverser = Reverser::new(&dumper, 0);
+        let mut output = Vec::new();
+
+        let parallel_err = reverse(&mut reverser, 3, dump.as_bytes(), &mut output).unwrap_err();
+
+        assert_eq!(parallel_err.to_string(), err.to_string());
+        assert!(err.to_string().contains("invalid hex char"));
+        assert_eq!(output, expected);
+
+        // A trailing `*` is only detected once every line was written
+        let dump = "00000000: 41  A\n*\n";
+        let err = reverse_all(&dumper, dump.as_bytes(), 2).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "malformed line: '*' must be followed by a line (line 2, column 2)"
+        );
+    }
+}
@@ -0,0 +1,597 @@
+use std::io::{self, BufRead, Read, Write};
+use std::mem;
+
+use crate::dumper::{BLOCK_SIZE, DumpState, LineContext, head, keep_tail};
+use crate::hex::{Patch, Reverser};
+use crate::{Dumper, Error};
+
+/// Adapter hex dumping every byte written through it to the inner writer.
+///
+/// Lines are written as soon as they are complete (along with the bytes after them needed by
+/// the charset). The final partial line, and anything following the lines (e.g., C include
+/// declarations), is written by `HexEncoder::finish`, or when the encoder is dropped.
+///
+/// Bytes are reported as written once buffered, so that they are never dumped twice when a
+/// write is retried. A failure writing their lines is reported by the next call instead.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut encoder = hxx::Dumper::new().cols(4).encoder(Vec::new());
+///
+/// encoder.write_all(b"hel")?;
+/// encoder.write_all(b"lo")?;
+///
+/// let output = encoder.finish()?;
+///
+/// assert_eq!(
+///     output,
+///     b"00000000: 6865 6c6c  hell\n\
+///       00000004: 6f         o\n"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct HexEncoder<W: Write> {
+    // Only taken out by `finish`
+    writer: Option<W>,
+    state: DumpState,
+    started: bool,
+    // Bytes written but not dumped yet, starting at `offset` in the data
+    pending: Vec<u8>,
+    offset: u64,
+    // Bytes before `pending`, to decode characters crossing the line boundaries
+    before: Vec<u8>,
+    // Failure writing the lines of bytes already reported as written
+    error: Option<Error>,
+}
+
+impl<W: Write> HexEncoder<W> {
+    /// Creates an encoder with the default `xxd` formatting.
+    pub fn new(writer: W) -> Self {
+        Self::with_dumper(&Dumper::new(), writer)
+    }
+
+    pub(crate) fn with_dumper(dumper: &Dumper, writer: W) -> Self {
+        let state = DumpState::new(dumper);
+        let line_format = state.line_format();
+        let capacity = line_format.cols + line_format.charset.context_len();
+
+        Self {
+            writer: Some(writer),
+            state,
+            started: false,
+            pending: Vec::with_capacity(capacity),
+            offset: 0,
+            before: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        self.writer
+            .as_ref()
+            .expect("writer is only taken by `finish`")
+    }
+
+    /// Writes the final partial line and anything following the lines, flushes the inner
+    /// writer and returns it.
+    ///
+    /// # Error
+    ///
+    /// This function returns an error if it fails to write to or flush the inner writer, or
+    /// failed to write the lines of bytes reported as written. Nothing more is written then.
+    pub fn finish(mut self) -> Result<W, Error> {
+        let result = match self.error.take() {
+            Some(err) => Err(err),
+            None => self.dump_pending(true),
+        };
+
+        // Taken even on failure, so that dropping the encoder never finishes it a second time
+        let writer = self
+            .writer
+            .take()
+            .expect("writer is only taken by `finish`");
+
+        result.map(|()| writer)
+    }
+
+    // Dumps the complete lines of `pending`, or every line if no more bytes will be written
+    fn dump_pending(&mut self, last: bool) -> Result<(), Error> {
+        let Self {
+            writer,
+            state,
+            started,
+            pending,
+            offset,
+            before,
+            ..
+        } = self;
+
+        let writer = writer.as_mut().expect("writer is only taken by `finish`");
+        let cols = state.line_format().cols;
+        let context_len = state.line_format().charset.context_len();
+
+        if !*started {
+            state.start();
+            *started = true;
+        }
+
+        let mut start = 0;
+
+        // Lines are only complete once the bytes after them are known
+        while pending.len() - start >= cols + context_len || (last && start < pending.len()) {
+            let end = (start + cols).min(pending.len());
+            let bytes = &pending[start..end];
+
+            let context = LineContext {
+                index: *offset,
+                before,
+                after: head(&pending[end..], context_len),
+            };
+
+            // A line is pushed even if writing the batch it completes fails, so it is never
+            // pushed again
+            let pushed = state.push(writer, bytes, context);
+
+            keep_tail(before, bytes, context_len);
+            *offset += bytes.len() as u64;
+            start = end;
+
+            if let Err(err) = pushed {
+                pending.drain(..start);
+                return Err(err);
+            }
+        }
+
+        pending.drain(..start);
+
+        if last {
+            state.end(writer)?;
+
+            writer
+                .flush()
+                .map_err(Error::io("failed to write to output"))?;
+        } else {
+            // Complete lines are written as soon as possible, rather than in batches
+            state.flush(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HexEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(err) = self.error.take() {
+            return Err(err.into());
+        }
+
+        self.pending.extend_from_slice(buf);
+        self.error = self.dump_pending(false).err();
+
+        Ok(buf.len())
+    }
+
+    /// Flushes the inner writer. The final partial line is only written by `finish`, since
+    /// more bytes may follow.
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err.into());
+        }
+
+        self.writer
+            .as_mut()
+            .expect("writer is only taken by `finish`")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for HexEncoder<W> {
+    fn drop(&mut self) {
+        // Errors cannot be reported when dropped, which is what `finish` is for
+        if self.writer.is_some() {
+            let _ = self.dump_pending(true);
+        }
+    }
+}
+
+/// Adapter reading the binary data of the hex dump read from the inner reader.
+///
+/// The hex dump is parsed one line at a time, as data is read. Offsets are expected to
+/// increase: gaps between lines are filled with zeros, and lines before the current position
+/// are reported as errors, like reversing to a stream. Gaps and lines skipped by `*` are
+/// produced as they are read, so they take no memory however large they are.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Read;
+///
+/// let dump = "00000000: 6865 6c6c  hell\n00000004: 6f         o\n";
+/// let mut decoder = hxx::Dumper::new().decoder(dump.as_bytes());
+///
+/// let mut output = String::new();
+/// decoder.read_to_string(&mut output)?;
+///
+/// assert_eq!(output, "hello");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct HexDecoder<R: BufRead> {
+    reader: R,
+    reverser: Reverser,
+    // Current line of the hex dump, as raw bytes
+    buf: Vec<u8>,
+    // Bytes decoded from the current line, not yet moved to `piece`
+    decoded: Decoded,
+    // Bytes being read, and the number of them already read
+    piece: Vec<u8>,
+    consumed: usize,
+    done: bool,
+}
+
+impl<R: BufRead> HexDecoder<R> {
+    /// Creates a decoder for hex dumps in the default `xxd` format.
+    pub fn new(reader: R) -> Self {
+        Self::with_dumper(&Dumper::new(), reader)
+    }
+
+    pub(crate) fn with_dumper(dumper: &Dumper, reader: R) -> Self {
+        Self {
+            reader,
+            reverser: Reverser::new(dumper, 0),
+            buf: Vec::with_capacity(1024),
+            decoded: Decoded::default(),
+            piece: Vec::with_capacity(1024),
+            consumed: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the inner reader, positioned after the last line parsed.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> BufRead for HexDecoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Lines may decode to no bytes (e.g., C include declarations)
+        while self.consumed == self.piece.len() && !self.done {
+            self.piece.clear();
+            self.consumed = 0;
+
+            // The run before the bytes of the line is read one block at a time
+            if self.decoded.run > 0 {
+                self.decoded.read_run(&mut self.piece);
+                continue;
+            }
+
+            if !self.decoded.bytes.is_empty() {
+                mem::swap(&mut self.piece, &mut self.decoded.bytes);
+                continue;
+            }
+
+            // Reset buffer since `read_until()` preserves buffer contents
+            self.buf.clear();
+
+            if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+                self.done = true;
+                self.reverser.finish()?;
+            } else {
+                self.reverser.decode_line(&self.buf, &mut self.decoded)?;
+            }
+        }
+
+        Ok(&self.piece[self.consumed..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.consumed = (self.consumed + amount).min(self.piece.len());
+    }
+}
+
+impl<R: BufRead> Read for HexDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+
+        Ok(len)
+    }
+}
+
+// Bytes decoded from a line of a hex dump: the run filling the gap (or the lines skipped by
+// `*`) before the line, kept as a length rather than in memory, then the bytes of the line
+#[derive(Default)]
+struct Decoded {
+    // Repeated to fill the run, which is made of zeros if empty
+    pattern: Vec<u8>,
+    // Bytes of the run left to read, and the position of the next one in `pattern`
+    run: u64,
+    phase: usize,
+    bytes: Vec<u8>,
+}
+
+impl Decoded {
+    fn start_run(&mut self, pattern: &[u8], len: u64) {
+        self.pattern.clear();
+        self.pattern.extend_from_slice(pattern);
+        self.run = len;
+        self.phase = 0;
+    }
+
+    // Moves the next block of the run to `piece`
+    fn read_run(&mut self, piece: &mut Vec<u8>) {
+        let len = self.run.min(BLOCK_SIZE as u64) as usize;
+
+        if self.pattern.is_empty() {
+            piece.resize(len, 0);
+        } else {
+            let mut left = len;
+
+            while left > 0 {
+                let chunk = &self.pattern[self.phase..];
+                let chunk = &chunk[..chunk.len().min(left)];
+
+                piece.extend_from_slice(chunk);
+                self.phase = (self.phase + chunk.len()) % self.pattern.len();
+                left -= chunk.len();
+            }
+        }
+
+        self.run -= len as u64;
+    }
+}
+
+impl Patch for Decoded {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.bytes.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn seek(&mut self, current: u64, target: u64) -> io::Result<()> {
+        let gap = target.checked_sub(current).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek backwards on non-seekable output",
+            )
+        })?;
+
+        self.start_run(&[], gap);
+        Ok(())
+    }
+
+    fn repeat(&mut self, bytes: &[u8], len: u64) -> io::Result<()> {
+        self.start_run(bytes, len);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Charset, Format};
+
+    // Writer failing its first write only
+    struct Flaky<W> {
+        inner: W,
+        failed: bool,
+    }
+
+    impl<W> Flaky<W> {
+        fn new(inner: W) -> Self {
+            Self {
+                inner,
+                failed: false,
+            }
+        }
+    }
+
+    impl<W: Write> Write for Flaky<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if !self.failed {
+                self.failed = true;
+                return Err(io::Error::other("disk full"));
+            }
+
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Writes `bytes` through an encoder in chunks of `chunk` bytes
+    fn encode(dumper: &Dumper, bytes: &[u8], chunk: usize) -> String {
+        let mut encoder = dumper.encoder(Vec::new());
+
+        for chunk in bytes.chunks(chunk) {
+            encoder.write_all(chunk).unwrap();
+        }
+
+        String::from_utf8(encoder.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_encoder_matches_dump() {
+        let mut bytes: Vec<u8> = "zero: ".bytes().collect();
+        bytes.extend([0; 64]);
+        bytes.extend("ünïcödé €".bytes().cycle().take(100));
+
+        let dumpers = [
+            Dumper::new(),
+            Dumper::new().cols(5).autoskip(true),
+            Dumper::new().cols(7).charset(Charset::Utf8),
+            Dumper::new().format(Format::Include).name("data"),
+            Dumper::new().format(Format::Plain),
+        ];
+
+        for dumper in &dumpers {
+            let expected = dumper.dump_bytes(&bytes);
+
+            for chunk in [1, 3, 16, 1000] {
+                assert_eq!(encode(dumper, &bytes, chunk), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoder_finishes_on_drop() {
+        let mut output = Vec::new();
+
+        {
+            let mut encoder = Dumper::new().format(Format::Include).encoder(&mut output);
+            encoder.write_all(b"hx").unwrap();
+        }
+
+        assert_eq!(output, b"  0x68, 0x78\n");
+    }
+
+    #[test]
+    fn test_encoder_reports_failures_once_buffered() {
+        let dumper = Dumper::new().cols(4);
+        let mut encoder = dumper.encoder(Flaky::new(Vec::new()));
+
+        assert_eq!(encoder.write(b"hello").unwrap(), 5);
+        assert!(encoder.write(b", world").is_err());
+
+        // Nothing is dumped twice, or lost
+        encoder.write_all(b", world").unwrap();
+        let output = encoder.finish().unwrap().inner;
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            dumper.dump_bytes(b"hello, world")
+        );
+    }
+
+    #[test]
+    fn test_encoder_recovers_from_failed_batch() {
+        // Enough lines for the batch to be written while they are pushed
+        let bytes: Vec<u8> = (0..=255).cycle().take(BLOCK_SIZE).collect();
+
+        for dumper in [
+            Dumper::new().squeeze(true),
+            Dumper::new().format(Format::Include).name("data"),
+        ] {
+            let mut encoder = dumper.encoder(Flaky::new(Vec::new()));
+
+            assert_eq!(encoder.write(&bytes).unwrap(), bytes.len());
+            assert!(encoder.flush().is_err());
+
+            encoder.write_all(&bytes).unwrap();
+            let output = encoder.finish().unwrap().inner;
+
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                dumper.dump_bytes(&[&bytes[..], &bytes].concat())
+            );
+        }
+    }
+
+    #[test]
+    fn test_encoder_not_finished_twice() {
+        let mut output = Vec::new();
+
+        {
+            let mut encoder = Dumper::new()
+                .format(Format::Include)
+                .name("data")
+                .encoder(Flaky::new(&mut output));
+
+            encoder.write_all(b"hx").unwrap();
+            assert!(encoder.finish().is_err());
+        }
+
+        // Dropping the encoder after a failed `finish` writes nothing more
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_decoder_round_trip() {
+        let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        let dumpers = [
+            Dumper::new(),
+            Dumper::new().cols(7).byte_groups(3).squeeze(true),
+            Dumper::new().format(Format::Bits),
+            Dumper::new().format(Format::LittleEndian),
+            Dumper::new().format(Format::Include).name("data"),
+            Dumper::new().format(Format::Plain),
+        ];
+
+        for dumper in &dumpers {
+            let dump = dumper.dump_bytes(&bytes);
+
+            let mut output = Vec::new();
+            dumper
+                .decoder(dump.as_bytes())
+                .read_to_end(&mut output)
+                .unwrap();
+
+            assert_eq!(output, bytes);
+        }
+    }
+
+    #[test]
+    fn test_decoder_fills_gaps() {
+        let dump = "00000002: 4142  AB\n00000006: 43  C\n";
+
+        let mut output = Vec::new();
+        HexDecoder::new(dump.as_bytes())
+            .read_to_end(&mut output)
+            .unwrap();
+
+        assert_eq!(output, b"\0\0AB\0\0C");
+    }
+
+    #[test]
+    fn test_decoder_large_gaps_and_runs() {
+        // Gaps and skipped lines are never held in memory as a whole
+        let dump = "00000000: 41  A\n0000ffffffffff00: 42  B\n";
+        let mut decoder = HexDecoder::new(dump.as_bytes());
+
+        let mut output = Vec::new();
+        (&mut decoder)
+            .take(1 << 20)
+            .read_to_end(&mut output)
+            .unwrap();
+
+        assert_eq!(output[0], b'A');
+        assert!(output[1..].iter().all(|&b| b == 0));
+
+        let dump = "00000000: 4142 4344  ABCD\n*\n00400000: 4142 4344  ABCD\n";
+        let mut output = Vec::new();
+        HexDecoder::new(dump.as_bytes())
+            .read_to_end(&mut output)
+            .unwrap();
+
+        assert_eq!(output, b"ABCD".repeat(0x10_0001));
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let dump = "00000004: 4142  AB\n00000000: 43  C\n";
+
+        let err = HexDecoder::new(dump.as_bytes())
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("cannot seek backwards"));
+
+        let err = HexDecoder::new("00000000: 4G  .\n".as_bytes())
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("(line 1, column 12)"));
+    }
+}
@@ -0,0 +1,53 @@
+// Fixtures shared by the tests of every module
+
+use std::cell::RefCell;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::{env, fs, process};
+
+// Output sink whose contents remain accessible after `Config` is consumed
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuf(Rc<RefCell<Cursor<Vec<u8>>>>);
+
+impl SharedBuf {
+    pub(crate) fn new(contents: &[u8]) -> Self {
+        Self(Rc::new(RefCell::new(Cursor::new(contents.to_vec()))))
+    }
+
+    pub(crate) fn contents(&self) -> Vec<u8> {
+        self.0.borrow().get_ref().clone()
+    }
+}
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SharedBuf {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.borrow_mut().seek(pos)
+    }
+}
+
+// Path of the temporary file `name`, which does not exist yet. Tests run concurrently, so
+// every test of the crate uses names of its own
+pub(crate) fn test_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("hxx-{}-{name}", process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Empty temporary directory `name`, named like `test_path`
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("hxx-{}-{name}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}